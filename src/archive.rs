@@ -0,0 +1,173 @@
+//! Contains the subcommands that export all the course manifests in the library to a single file
+//! and import them back into the directory structure of the repository.
+
+use std::{collections::BTreeSet, fs, path::Path};
+
+use anyhow::{bail, Context, Result};
+use trane::data::CourseManifest;
+
+use crate::{
+    changes::FileChanges,
+    checks,
+    library::{self, MANIFEST_FILE},
+    output::status,
+};
+
+/// Exports the manifests of all the transcription courses to a single JSON file containing an
+/// array of manifests, sorted by their path in the repository. The manifests are read directly
+/// from disk instead of from trane, which adds generated metadata when opening the library.
+pub(crate) fn export_library(root: &Path, output: &Path) -> Result<()> {
+    let manifests = library::read_all_manifests(&library::courses_root(root)?)?
+        .into_iter()
+        .map(|(_, manifest)| manifest)
+        .filter(|manifest| library::transcription_config(manifest).is_some())
+        .collect::<Vec<_>>();
+    let buf = library::to_pretty_json(&manifests)?;
+    fs::write(output, buf)
+        .with_context(|| format!("failed to write library export to {}", output.display()))?;
+    status!(
+        "Exported {} courses to {}",
+        manifests.len(),
+        output.display()
+    );
+    Ok(())
+}
+
+/// Imports the course manifests from a JSON file created by `export_library`, writing each of them
+/// to the directory matching its ID. Existing courses are only overwritten if `overwrite` is true.
+pub(crate) fn import_library(
+    root: &Path,
+    changes: FileChanges,
+    input: &Path,
+    overwrite: bool,
+) -> Result<()> {
+    let contents = fs::read_to_string(input)
+        .with_context(|| format!("failed to read library export at {}", input.display()))?;
+    let manifests: Vec<CourseManifest> = serde_json::from_str(&contents)
+        .with_context(|| format!("failed to parse library export at {}", input.display()))?;
+
+    // Check that every ID is well-formed, so that no course is written outside the courses
+    // directory, and that no course would be overwritten before writing anything.
+    let courses_root = library::courses_root(root)?;
+    let mut directories = BTreeSet::new();
+    for manifest in &manifests {
+        if let Some(segment) = checks::invalid_course_id_segment(&manifest.id) {
+            bail!(
+                "invalid course id {}: segment \"{segment}\" must contain only lowercase letters, \
+                digits, and underscores",
+                manifest.id
+            );
+        }
+        let directory = library::course_directory(&courses_root, &manifest.id);
+        let manifest_path = directory.join(MANIFEST_FILE);
+        if !directories.insert(directory) {
+            bail!(
+                "course {} appears more than once in the export",
+                manifest.id
+            );
+        }
+        if !overwrite && manifest_path.exists() {
+            bail!("course already exists at {}", manifest_path.display());
+        }
+    }
+
+    for manifest in &manifests {
+        let directory = library::course_directory(&courses_root, &manifest.id);
+        changes.create_dir_all(&directory)?;
+        changes.write_manifest(&directory.join(MANIFEST_FILE), manifest)?;
+    }
+    status!(
+        "Imported {} courses from {}",
+        manifests.len(),
+        input.display()
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use anyhow::Result;
+    use std::fs;
+
+    use super::*;
+    use crate::library::COURSES_DIRECTORY;
+
+    /// Verifies that exporting a library and importing it into an empty repository recreates the
+    /// same manifests, and that existing courses are only overwritten when requested.
+    #[test]
+    fn export_and_import() -> Result<()> {
+        let source = tempfile::tempdir()?;
+        let course_directory = source.path().join(COURSES_DIRECTORY).join("jazz/blues");
+        fs::create_dir_all(&course_directory)?;
+        fs::copy(
+            Path::new(COURSES_DIRECTORY)
+                .join("folk/adrianne_lenker/songs")
+                .join(MANIFEST_FILE),
+            course_directory.join(MANIFEST_FILE),
+        )?;
+        let mut manifest = library::read_manifest(&course_directory.join(MANIFEST_FILE))?;
+        manifest.id = "trane::transcription::jazz::blues".into();
+        library::write_manifest(&course_directory.join(MANIFEST_FILE), &manifest, false)?;
+        let export = source.path().join("export.json");
+        export_library(source.path(), &export)?;
+
+        let destination = tempfile::tempdir()?;
+        fs::create_dir(destination.path().join(COURSES_DIRECTORY))?;
+        import_library(destination.path(), FileChanges::default(), &export, false)?;
+        let imported = library::read_manifest(
+            &destination
+                .path()
+                .join(COURSES_DIRECTORY)
+                .join("jazz/blues")
+                .join(MANIFEST_FILE),
+        )?;
+        assert_eq!(imported, manifest);
+
+        assert!(
+            import_library(destination.path(), FileChanges::default(), &export, false).is_err()
+        );
+        import_library(destination.path(), FileChanges::default(), &export, true)?;
+        Ok(())
+    }
+
+    /// Verifies that an export with a malformed or repeated course ID is rejected before any course
+    /// is written.
+    #[test]
+    fn import_invalid_ids() -> Result<()> {
+        let manifest = library::read_manifest(
+            &Path::new(COURSES_DIRECTORY)
+                .join("folk/adrianne_lenker/songs")
+                .join(MANIFEST_FILE),
+        )?;
+        let destination = tempfile::tempdir()?;
+        let courses_root = destination.path().join(COURSES_DIRECTORY);
+        fs::create_dir(&courses_root)?;
+        let export = destination.path().join("export.json");
+
+        for ids in [
+            vec![
+                "trane::transcription::jazz::blues",
+                "trane::transcription::..::..::x",
+            ],
+            vec!["trane::transcription::jazz::blues", ""],
+            vec![
+                "trane::transcription::jazz::blues",
+                "trane::transcription::jazz::blues",
+            ],
+        ] {
+            let manifests = ids
+                .into_iter()
+                .map(|id| CourseManifest {
+                    id: id.into(),
+                    ..manifest.clone()
+                })
+                .collect::<Vec<_>>();
+            fs::write(&export, library::to_pretty_json(&manifests)?)?;
+            assert!(
+                import_library(destination.path(), FileChanges::default(), &export, true).is_err()
+            );
+            assert!(library::find_manifests(&courses_root)?.is_empty());
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,106 @@
+//! Contains the hidden `bench` subcommand, which measures how long it takes trane to load the
+//! library, so that regressions in load performance can be tracked as the library grows.
+
+use std::{path::Path, time::Instant};
+
+use anyhow::{bail, Result};
+use serde::Serialize;
+use trane::course_library::CourseLibrary;
+
+use crate::{
+    exit::{Failure, FailureKind},
+    library,
+    reports::ReportFormat,
+};
+
+/// The load durations measured by the `bench` subcommand, in milliseconds.
+#[derive(Debug, PartialEq, Serialize)]
+struct BenchReport {
+    /// The number of times the library was loaded.
+    iterations: usize,
+
+    /// The shortest load duration.
+    min_ms: f64,
+
+    /// The median load duration.
+    median_ms: f64,
+
+    /// The longest load duration.
+    max_ms: f64,
+
+    /// The number of exercises in the library.
+    exercise_count: usize,
+}
+
+/// Summarizes the load durations, in milliseconds, of the library with the given number of
+/// exercises. For an even number of durations, the median is the mean of the two middle ones.
+fn summarize(mut durations: Vec<f64>, exercise_count: usize) -> BenchReport {
+    durations.sort_by(f64::total_cmp);
+    let middle = durations.len() / 2;
+    let median_ms = if durations.len().is_multiple_of(2) {
+        (durations[middle - 1] + durations[middle]) / 2.0
+    } else {
+        durations[middle]
+    };
+    BenchReport {
+        iterations: durations.len(),
+        min_ms: durations[0],
+        median_ms,
+        max_ms: durations[durations.len() - 1],
+        exercise_count,
+    }
+}
+
+/// Loads the library the given number of times and prints the minimum, median, and maximum load
+/// durations along with the number of exercises in the library.
+pub(crate) fn bench_library(root: &Path, iterations: usize, format: ReportFormat) -> Result<()> {
+    if iterations == 0 {
+        bail!(Failure::new(
+            FailureKind::Usage,
+            "the number of iterations must be at least 1"
+        ));
+    }
+
+    let mut durations = Vec::with_capacity(iterations);
+    let mut exercise_count = 0;
+    for _ in 0..iterations {
+        let start = Instant::now();
+        let trane = library::open_library(root)?;
+        durations.push(start.elapsed().as_secs_f64() * 1000.0);
+        exercise_count = trane.get_all_exercise_ids(None).len();
+    }
+
+    let report = summarize(durations, exercise_count);
+    match format {
+        ReportFormat::Plain => {
+            println!(
+                "Loaded the library {} times: min {:.1} ms, median {:.1} ms, max {:.1} ms",
+                report.iterations, report.min_ms, report.median_ms, report.max_ms
+            );
+            println!("Exercises: {}", report.exercise_count);
+        }
+        ReportFormat::Json => println!("{}", serde_json::to_string_pretty(&report)?),
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::{summarize, BenchReport};
+
+    /// Verifies that the minimum, median, and maximum durations are computed.
+    #[test]
+    fn summarize_durations() {
+        assert_eq!(
+            summarize(vec![30.0, 10.0, 20.0], 4),
+            BenchReport {
+                iterations: 3,
+                min_ms: 10.0,
+                median_ms: 20.0,
+                max_ms: 30.0,
+                exercise_count: 4,
+            }
+        );
+        assert_eq!(summarize(vec![40.0, 10.0, 20.0, 30.0], 4).median_ms, 25.0);
+    }
+}
@@ -0,0 +1,133 @@
+//! Contains an on-disk cache of the results of previous link verifications, so that links verified
+//! recently do not need to be checked again on every run.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use crate::library;
+
+/// The directory, relative to the root of the repository, where the cache is stored.
+pub(crate) const CACHE_DIRECTORY: &str = ".trane-transcription-cache";
+
+/// The name of the file storing the link verification results.
+const LINKS_FILE: &str = "links.json";
+
+/// The result of the last verification of a link.
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
+struct CacheEntry {
+    /// The time of the last verification, in seconds since the Unix epoch.
+    verified_at: u64,
+
+    /// Whether the link was valid during the last verification.
+    valid: bool,
+}
+
+/// A cache of link verification results keyed by the URL of the link.
+#[derive(Debug)]
+pub(crate) struct LinkCache {
+    /// The path to the file backing the cache.
+    path: PathBuf,
+
+    /// The cached results.
+    entries: BTreeMap<String, CacheEntry>,
+}
+
+/// Returns the current time in seconds since the Unix epoch.
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+impl LinkCache {
+    /// Opens the cache stored under the given repository root. A missing cache file results in an
+    /// empty cache.
+    pub fn open(root: &Path) -> Result<Self> {
+        let path = root.join(CACHE_DIRECTORY).join(LINKS_FILE);
+        let entries = if path.exists() {
+            let contents = fs::read_to_string(&path)
+                .with_context(|| format!("failed to read link cache at {}", path.display()))?;
+            serde_json::from_str(&contents)
+                .with_context(|| format!("failed to parse link cache at {}", path.display()))?
+        } else {
+            BTreeMap::new()
+        };
+        Ok(Self { path, entries })
+    }
+
+    /// Returns whether the link was successfully verified within the given time-to-live.
+    pub fn is_fresh(&self, url: &str, ttl: Duration) -> bool {
+        match self.entries.get(url) {
+            Some(entry) => entry.valid && now().saturating_sub(entry.verified_at) < ttl.as_secs(),
+            None => false,
+        }
+    }
+
+    /// Returns whether the last verification of the link failed, regardless of when it happened.
+    pub fn is_broken(&self, url: &str) -> bool {
+        self.entries.get(url).is_some_and(|entry| !entry.valid)
+    }
+
+    /// Records the result of verifying the link at the current time.
+    pub fn record(&mut self, url: &str, valid: bool) {
+        self.entries.insert(
+            url.to_string(),
+            CacheEntry {
+                verified_at: now(),
+                valid,
+            },
+        );
+    }
+
+    /// Writes the cache to disk. The contents are written to a temporary file first and then
+    /// renamed, so an interrupted run cannot leave a corrupted cache behind.
+    pub fn save(&self) -> Result<()> {
+        let directory = self.path.parent().unwrap_or(Path::new("."));
+        fs::create_dir_all(directory).with_context(|| {
+            format!(
+                "failed to create cache directory at {}",
+                directory.display()
+            )
+        })?;
+        let contents = serde_json::to_string_pretty(&self.entries)
+            .context("failed to serialize link cache")?;
+        library::write_atomically(&self.path, contents.as_bytes(), false)
+            .with_context(|| format!("failed to write link cache to {}", self.path.display()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use anyhow::Result;
+    use std::time::Duration;
+
+    use super::*;
+
+    /// Verifies that cached results survive a round trip to disk, that only valid, recent results
+    /// are considered fresh, and that only invalid results are considered broken.
+    #[test]
+    fn save_and_open() -> Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        let mut cache = LinkCache::open(temp_dir.path())?;
+        cache.record("https://valid.com", true);
+        cache.record("https://invalid.com", false);
+        cache.save()?;
+
+        let cache = LinkCache::open(temp_dir.path())?;
+        let ttl = Duration::from_secs(60);
+        assert!(cache.is_fresh("https://valid.com", ttl));
+        assert!(!cache.is_fresh("https://invalid.com", ttl));
+        assert!(!cache.is_fresh("https://unknown.com", ttl));
+        assert!(!cache.is_fresh("https://valid.com", Duration::ZERO));
+        assert!(cache.is_broken("https://invalid.com"));
+        assert!(!cache.is_broken("https://valid.com"));
+        assert!(!cache.is_broken("https://unknown.com"));
+        Ok(())
+    }
+}
@@ -0,0 +1,95 @@
+//! Contains the changes to the filesystem made by the subcommands that modify the courses. When
+//! running in dry-run mode, the changes are printed instead of applied, so they can be reviewed
+//! first.
+
+use std::{fs, path::Path};
+
+use anyhow::{Context, Result};
+use trane::data::CourseManifest;
+
+use crate::library::{self, AdditionalLinks};
+
+/// Applies or, in dry-run mode, prints the changes to the filesystem.
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct FileChanges {
+    /// Whether to print the changes instead of applying them.
+    pub dry_run: bool,
+
+    /// Whether to keep a `.bak` copy of the previous contents of the files that are overwritten.
+    pub backup: bool,
+}
+
+impl FileChanges {
+    /// Creates the directory and all its missing parents.
+    pub fn create_dir_all(&self, directory: &Path) -> Result<()> {
+        if self.dry_run {
+            println!("Would create directory {}", directory.display());
+            return Ok(());
+        }
+        fs::create_dir_all(directory)
+            .with_context(|| format!("failed to create directory at {}", directory.display()))
+    }
+
+    /// Writes the course manifest to the given path. In dry-run mode, the exact contents that
+    /// would be written are printed.
+    pub fn write_manifest(&self, path: &Path, manifest: &CourseManifest) -> Result<()> {
+        if self.dry_run {
+            let buf = library::serialize_manifest(manifest)?;
+            println!("Would write {}:", path.display());
+            println!("{}", String::from_utf8_lossy(&buf));
+            return Ok(());
+        }
+        library::write_manifest(path, manifest, self.backup)
+    }
+
+    /// Writes the contents to the given file atomically. In dry-run mode, the contents that would
+    /// be written are printed.
+    pub fn write_file(&self, path: &Path, contents: &[u8]) -> Result<()> {
+        if self.dry_run {
+            println!("Would write {}:", path.display());
+            println!("{}", String::from_utf8_lossy(contents));
+            return Ok(());
+        }
+        library::write_atomically(path, contents, self.backup)
+    }
+
+    /// Writes the additional links of a course to the given path, or deletes the file if the course
+    /// has none left.
+    pub fn write_additional_links(
+        &self,
+        path: &Path,
+        additional_links: &AdditionalLinks,
+    ) -> Result<()> {
+        if !additional_links.is_empty() {
+            return self.write_file(path, &library::to_pretty_json(additional_links)?);
+        }
+        if !path.exists() {
+            return Ok(());
+        }
+        if self.dry_run {
+            println!("Would delete {}", path.display());
+            return Ok(());
+        }
+        fs::remove_file(path).with_context(|| format!("failed to delete {}", path.display()))
+    }
+
+    /// Moves the file or directory at `from` to `to`.
+    pub fn rename(&self, from: &Path, to: &Path) -> Result<()> {
+        if self.dry_run {
+            println!("Would move {} to {}", from.display(), to.display());
+            return Ok(());
+        }
+        fs::rename(from, to)
+            .with_context(|| format!("failed to move {} to {}", from.display(), to.display()))
+    }
+
+    /// Deletes the directory and all its contents.
+    pub fn remove_dir_all(&self, directory: &Path) -> Result<()> {
+        if self.dry_run {
+            println!("Would delete directory {}", directory.display());
+            return Ok(());
+        }
+        fs::remove_dir_all(directory)
+            .with_context(|| format!("failed to delete directory at {}", directory.display()))
+    }
+}
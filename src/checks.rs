@@ -0,0 +1,480 @@
+//! Contains the checks run by the `verify-courses` subcommand on the course manifests. The checks
+//! on the whole library print the issues they find and return the number of issues. The checks on
+//! each course are run in parallel and return the issues found, which are printed in order.
+
+use std::{
+    collections::BTreeMap,
+    fmt, fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Result;
+use rayon::prelude::*;
+use trane::data::{
+    course_generator::transcription::{TranscriptionAsset, TranscriptionPassages},
+    CourseManifest,
+};
+use ustr::Ustr;
+
+use crate::{graph::DependencyGraph, library, links};
+
+/// Verifies that the transcription dependencies of all courses form a valid graph, that is, one
+/// without cycles and in which every dependency exists.
+pub(crate) fn verify_dependency_graph(manifests: &[CourseManifest]) -> usize {
+    let graph = DependencyGraph::from_manifests(manifests);
+    let cycles = graph.find_cycles();
+    for cycle in &cycles {
+        let cycle = cycle.iter().map(|id| id.as_str()).collect::<Vec<_>>();
+        println!("Found dependency cycle: {}", cycle.join(" -> "));
+    }
+    let dangling = graph.find_dangling_dependencies();
+    for (course_id, dependency) in &dangling {
+        println!("Course {course_id} depends on missing course {dependency}");
+    }
+    cycles.len() + dangling.len()
+}
+
+/// Verifies that the short ID of every inlined passage is unique across the whole library.
+pub(crate) fn verify_unique_short_ids(manifests: &[CourseManifest]) -> usize {
+    // Map each short ID to the courses in which it appears.
+    let mut short_ids: BTreeMap<&str, Vec<Ustr>> = BTreeMap::new();
+    for manifest in manifests {
+        let Some(config) = library::transcription_config(manifest) else {
+            continue;
+        };
+        for passages in &config.inlined_passages {
+            short_ids
+                .entry(passages.asset.short_id())
+                .or_default()
+                .push(manifest.id);
+        }
+    }
+
+    let mut duplicates = 0;
+    for (short_id, course_ids) in short_ids {
+        if course_ids.len() > 1 {
+            duplicates += 1;
+            let course_ids = course_ids.iter().map(|id| id.as_str()).collect::<Vec<_>>();
+            println!(
+                "Short ID {short_id} is used more than once in courses: {}",
+                course_ids.join(", ")
+            );
+        }
+    }
+    if duplicates > 0 {
+        println!("Found {duplicates} duplicate short_ids");
+    }
+    duplicates
+}
+
+/// Verifies that no two passages in the library refer to the same video. YouTube links are compared
+/// by the ID of the video, so different forms of the same link are also reported.
+pub(crate) fn verify_unique_external_links(
+    courses_root: &Path,
+    manifests: &[CourseManifest],
+) -> Result<usize> {
+    // Map each canonical link to the course and short ID of the passages in which it appears.
+    let mut links: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for manifest in manifests {
+        let Some(config) = library::transcription_config(manifest) else {
+            continue;
+        };
+        let additional_links = library::read_additional_links(courses_root, &manifest.id)?;
+        for passages in &config.inlined_passages {
+            let location = format!("{}/{}", manifest.id, passages.asset.short_id());
+            for link in library::passage_links(&additional_links, passages) {
+                // A passage may link to the same video on more than one platform.
+                let locations = links.entry(links::canonical_link(link.url())).or_default();
+                if !locations.contains(&location) {
+                    locations.push(location.clone());
+                }
+            }
+        }
+    }
+
+    let mut duplicates = 0;
+    for (link, passages) in links {
+        if passages.len() > 1 {
+            duplicates += 1;
+            println!(
+                "Link {link} is used by more than one passage: {}",
+                passages.join(", ")
+            );
+        }
+    }
+    Ok(duplicates)
+}
+
+/// A check run on each course independently of the rest of the library.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum CourseCheck {
+    /// Checks that the manifest is in the directory derived from the course ID. A mismatch usually
+    /// means the directory was moved without updating the ID.
+    Location,
+
+    /// Checks that every segment of the course ID is non-empty and contains only lowercase letters,
+    /// digits, and underscores.
+    IdFormat,
+
+    /// Checks that the course lists at least one author.
+    Authors,
+
+    /// Checks that the short ID of every inlined track is valid. An empty short ID breaks the
+    /// tracking of progress in trane.
+    ShortIdFormat,
+
+    /// Checks that the passage directory, if the course sets one, exists and contains files, and
+    /// that every passage file in it can be read by trane. The directory is resolved relative to
+    /// the directory of the course manifest.
+    PassageDirectory,
+}
+
+impl CourseCheck {
+    /// All the per-course checks.
+    pub const ALL: [Self; 5] = [
+        Self::Location,
+        Self::IdFormat,
+        Self::Authors,
+        Self::ShortIdFormat,
+        Self::PassageDirectory,
+    ];
+}
+
+/// An issue found in a course by one of the per-course checks.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) struct Finding {
+    /// The ID of the course with the issue.
+    pub course_id: Ustr,
+
+    /// The check that found the issue.
+    pub check: CourseCheck,
+
+    /// The description of the issue.
+    pub message: String,
+}
+
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Course {}: {}", self.course_id, self.message)
+    }
+}
+
+/// Returns whether the short ID is non-empty and contains only alphanumeric characters, dashes, and
+/// underscores.
+pub(crate) fn is_valid_short_id(short_id: &str) -> bool {
+    !short_id.is_empty()
+        && short_id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// Returns the first segment of the course ID that is empty or contains characters other than
+/// lowercase letters, digits, and underscores, or `None` if the ID is valid.
+pub(crate) fn invalid_course_id_segment(id: &str) -> Option<&str> {
+    id.split("::").find(|segment| {
+        segment.is_empty()
+            || !segment
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+    })
+}
+
+/// Returns whether the directory exists and contains at least one file.
+fn has_files(directory: &Path) -> bool {
+    fs::read_dir(directory).is_ok_and(|mut entries| {
+        entries.any(|entry| entry.is_ok_and(|entry| entry.path().is_file()))
+    })
+}
+
+/// Returns the descriptions of the issues in the passage files, that is, the JSON files directly
+/// inside the passage directory. Each file must contain the passages of a track with a valid and
+/// unique short ID, a track name, and, if it has one, an external link with an `http` or `https`
+/// scheme. The links are not fetched, so links to resources that no longer exist are not reported.
+fn check_passage_files(passage_directory: &Path) -> Vec<String> {
+    let Ok(entries) = fs::read_dir(passage_directory) else {
+        return vec![];
+    };
+    let mut paths = entries
+        .filter_map(|entry| Some(entry.ok()?.path()))
+        .filter(|path| {
+            path.is_file()
+                && path
+                    .extension()
+                    .is_some_and(|extension| extension.eq_ignore_ascii_case("json"))
+        })
+        .collect::<Vec<_>>();
+    paths.sort();
+
+    let mut issues = vec![];
+    let mut seen_short_ids = BTreeMap::new();
+    for path in paths {
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        let contents = match fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(err) => {
+                issues.push(format!("failed to read passage file {name}: {err}"));
+                continue;
+            }
+        };
+        let passages: TranscriptionPassages = match serde_json::from_str(&contents) {
+            Ok(passages) => passages,
+            Err(err) => {
+                issues.push(format!("passage file {name} is not a valid passage: {err}"));
+                continue;
+            }
+        };
+
+        let TranscriptionAsset::Track {
+            short_id,
+            track_name,
+            external_link,
+            ..
+        } = &passages.asset;
+        if !is_valid_short_id(short_id) {
+            issues.push(format!(
+                "passage file {name} has an invalid short ID \"{short_id}\""
+            ));
+        }
+        if track_name.trim().is_empty() {
+            issues.push(format!("passage file {name} has an empty track name"));
+        }
+        if let Some(link) = external_link {
+            let url = link.url().trim();
+            if !url.starts_with("https://") && !url.starts_with("http://") {
+                issues.push(format!(
+                    "passage file {name} has an invalid external link \"{url}\""
+                ));
+            }
+        }
+        if let Some(other) = seen_short_ids.insert(short_id.clone(), name.to_string()) {
+            issues.push(format!(
+                "passage files {other} and {name} have the same short ID \"{short_id}\""
+            ));
+        }
+    }
+    issues
+}
+
+/// Runs the given check on the course whose manifest is at `path` and returns the descriptions of
+/// the issues found.
+fn run_check(
+    check: CourseCheck,
+    courses_root: &Path,
+    path: &Path,
+    manifest: &CourseManifest,
+) -> Vec<String> {
+    match check {
+        CourseCheck::Location => {
+            let expected = library::manifest_path(courses_root, &manifest.id);
+            if expected == path {
+                vec![]
+            } else {
+                vec![format!(
+                    "expected at {}, but found at {}",
+                    expected.display(),
+                    path.display()
+                )]
+            }
+        }
+        CourseCheck::IdFormat => match invalid_course_id_segment(&manifest.id) {
+            Some(segment) => vec![format!(
+                "invalid segment \"{segment}\" in the ID, segments must contain only lowercase \
+                letters, digits, and underscores"
+            )],
+            None => vec![],
+        },
+        CourseCheck::Authors => {
+            if manifest
+                .authors
+                .as_ref()
+                .is_none_or(|authors| authors.is_empty())
+            {
+                vec!["does not list any authors".to_string()]
+            } else {
+                vec![]
+            }
+        }
+        CourseCheck::ShortIdFormat => {
+            let Some(config) = library::transcription_config(manifest) else {
+                return vec![];
+            };
+            config
+                .inlined_passages
+                .iter()
+                .enumerate()
+                .filter_map(|(position, passages)| {
+                    let TranscriptionAsset::Track { short_id, .. } = &passages.asset;
+                    (!is_valid_short_id(short_id)).then(|| {
+                        format!("passage {position} has an invalid short ID \"{short_id}\"")
+                    })
+                })
+                .collect()
+        }
+        CourseCheck::PassageDirectory => {
+            let Some(config) = library::transcription_config(manifest) else {
+                return vec![];
+            };
+            if config.passage_directory.is_empty() {
+                return vec![];
+            }
+            let course_directory = path.parent().unwrap_or(Path::new("."));
+            let passage_directory = course_directory.join(&config.passage_directory);
+            if !passage_directory.is_dir() {
+                vec![format!(
+                    "missing passage directory at {}",
+                    passage_directory.display()
+                )]
+            } else if !has_files(&passage_directory) {
+                vec![format!(
+                    "empty passage directory at {}",
+                    passage_directory.display()
+                )]
+            } else {
+                check_passage_files(&passage_directory)
+            }
+        }
+    }
+}
+
+/// Runs the given checks on the course whose manifest is at `path` and returns the issues found.
+/// Only the course itself is inspected, so courses can be checked in any order.
+pub(crate) fn check_course(
+    courses_root: &Path,
+    path: &Path,
+    manifest: &CourseManifest,
+    checks: &[CourseCheck],
+) -> Vec<Finding> {
+    checks
+        .iter()
+        .flat_map(|check| {
+            run_check(*check, courses_root, path, manifest)
+                .into_iter()
+                .map(|message| Finding {
+                    course_id: manifest.id,
+                    check: *check,
+                    message,
+                })
+        })
+        .collect()
+}
+
+/// Runs the given checks on all the courses in parallel and prints the issues found, sorted by
+/// course ID so the output does not depend on the order in which the courses were checked.
+pub(crate) fn verify_each_course(
+    courses_root: &Path,
+    manifests: &[(PathBuf, CourseManifest)],
+    checks: &[CourseCheck],
+) -> Vec<Finding> {
+    let mut findings = manifests
+        .par_iter()
+        .flat_map_iter(|(path, manifest)| check_course(courses_root, path, manifest, checks))
+        .collect::<Vec<_>>();
+    findings.sort();
+    for finding in &findings {
+        println!("{finding}");
+    }
+    findings
+}
+
+#[cfg(test)]
+mod test {
+    use anyhow::Result;
+    use std::{fs, path::Path};
+    use trane::data::CourseManifestBuilder;
+    use ustr::Ustr;
+
+    use super::{
+        check_course, check_passage_files, invalid_course_id_segment, is_valid_short_id,
+        CourseCheck,
+    };
+
+    /// Verifies that only non-empty short IDs with the allowed characters are valid.
+    #[test]
+    fn valid_short_ids() {
+        assert!(is_valid_short_id("dragon_eyes"));
+        assert!(is_valid_short_id("track-02"));
+        assert!(!is_valid_short_id(""));
+        assert!(!is_valid_short_id("dragon eyes"));
+        assert!(!is_valid_short_id("dragon::eyes"));
+    }
+
+    /// Verifies that the offending segment of a malformed course ID is returned.
+    #[test]
+    fn invalid_course_ids() {
+        assert_eq!(
+            invalid_course_id_segment("trane::transcription::folk::nick_drake_2"),
+            None
+        );
+        assert_eq!(
+            invalid_course_id_segment("trane::transcription::Folk::nick_drake"),
+            Some("Folk")
+        );
+        assert_eq!(
+            invalid_course_id_segment("trane::transcription::folk::nick drake"),
+            Some("nick drake")
+        );
+        assert_eq!(
+            invalid_course_id_segment("trane::transcription::folk::"),
+            Some("")
+        );
+    }
+
+    /// Verifies that the per-course checks find the issues in a course and skip disabled checks.
+    #[test]
+    fn check_course_findings() {
+        let courses_root = Path::new("courses");
+        let manifest = CourseManifestBuilder::default()
+            .id(Ustr::from("trane::transcription::a::b"))
+            .name("".to_string())
+            .build()
+            .unwrap();
+
+        let path = courses_root.join("a/b/course_manifest.json");
+        let findings = check_course(courses_root, &path, &manifest, &CourseCheck::ALL);
+        assert_eq!(
+            findings
+                .iter()
+                .map(|finding| finding.check)
+                .collect::<Vec<_>>(),
+            vec![CourseCheck::Authors]
+        );
+
+        let path = courses_root.join("c/course_manifest.json");
+        let findings = check_course(courses_root, &path, &manifest, &[CourseCheck::Location]);
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].check, CourseCheck::Location);
+    }
+
+    /// Verifies that malformed passage files and duplicate short IDs are reported.
+    #[test]
+    fn passage_file_issues() -> Result<()> {
+        let directory = tempfile::tempdir()?;
+        let track = |short_id: &str, link: &str| {
+            format!(
+                "{{\"asset\": {{\"Track\": {{\"short_id\": \"{short_id}\", \
+                \"track_name\": \"Track\", \"external_link\": {{\"YouTube\": \"{link}\"}}}}}}}}"
+            )
+        };
+        fs::write(
+            directory.path().join("a.json"),
+            track("a", "https://youtu.be/h8Pv04Tj5GE"),
+        )?;
+        fs::write(directory.path().join("notes.txt"), "not a passage")?;
+        assert!(check_passage_files(directory.path()).is_empty());
+
+        fs::write(directory.path().join("b.json"), track("a", "not a link"))?;
+        fs::write(directory.path().join("c.json"), "{")?;
+        let issues = check_passage_files(directory.path());
+        assert_eq!(issues.len(), 3);
+        assert_eq!(
+            issues[0],
+            "passage file b.json has an invalid external link \"not a link\""
+        );
+        assert_eq!(
+            issues[1],
+            "passage files a.json and b.json have the same short ID \"a\""
+        );
+        assert!(issues[2].starts_with("passage file c.json is not a valid passage"));
+        Ok(())
+    }
+}
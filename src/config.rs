@@ -0,0 +1,124 @@
+//! Contains the optional configuration file, which sets the defaults of some of the flags so they
+//! do not have to be passed on every invocation. Flags given on the command line take precedence
+//! over the values in the file.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+
+use crate::{links::VerifyLinksOptions, lint};
+
+/// The name of the configuration file looked up in the root of the repository.
+pub(crate) const CONFIG_FILE: &str = "trane-transcription.toml";
+
+/// The defaults read from the configuration file. Every field is optional.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct Config {
+    /// The root of the repository, relative to the directory containing the configuration file.
+    pub root: Option<PathBuf>,
+
+    /// The number of links to verify in parallel.
+    pub jobs: Option<usize>,
+
+    /// The number of seconds to wait to connect to a host and for each read of the response.
+    pub timeout: Option<u64>,
+
+    /// The number of days for which a successful verification is cached.
+    pub cache_ttl: Option<u64>,
+
+    /// Whether to download the page of each YouTube video to detect age-restricted videos.
+    pub check_age_restriction: Option<bool>,
+
+    /// The values allowed in the `course_series` metadata key, replacing the built-in list.
+    pub course_series: Option<Vec<String>>,
+}
+
+impl Config {
+    /// Reads the configuration file at the given path. If `required` is false, a missing file
+    /// results in the default configuration.
+    pub fn open(path: &Path, required: bool) -> Result<Self> {
+        if !required && !path.exists() {
+            return Ok(Self::default());
+        }
+        let contents = fs::read_to_string(path)
+            .with_context(|| format!("failed to read config file at {}", path.display()))?;
+        let mut config: Self = toml::from_str(&contents)
+            .with_context(|| format!("failed to parse config file at {}", path.display()))?;
+        if let (Some(root), Some(parent)) = (&config.root, path.parent()) {
+            config.root = Some(parent.join(root));
+        }
+        Ok(config)
+    }
+
+    /// Returns the default options used to verify the links, with the values in the configuration
+    /// file applied.
+    pub fn link_options(&self) -> VerifyLinksOptions {
+        let defaults = VerifyLinksOptions::default();
+        VerifyLinksOptions {
+            jobs: self.jobs.unwrap_or(defaults.jobs),
+            timeout: self.timeout.map_or(defaults.timeout, Duration::from_secs),
+            cache_ttl: self.cache_ttl.map_or(defaults.cache_ttl, |days| {
+                Duration::from_secs(days * 24 * 60 * 60)
+            }),
+            check_age_restriction: self
+                .check_age_restriction
+                .unwrap_or(defaults.check_age_restriction),
+            ..defaults
+        }
+    }
+
+    /// Returns the values allowed in the `course_series` metadata key.
+    pub fn known_course_series(&self) -> Vec<String> {
+        match &self.course_series {
+            Some(series) => series.clone(),
+            None => lint::KNOWN_COURSE_SERIES
+                .iter()
+                .map(|series| series.to_string())
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use anyhow::Result;
+    use std::{fs, path::Path, time::Duration};
+
+    use super::{Config, CONFIG_FILE};
+
+    /// Verifies that the values in the config file override the defaults and that the root is
+    /// resolved relative to the directory containing the file.
+    #[test]
+    fn open_config() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join(CONFIG_FILE);
+        assert!(Config::open(&path, false)?.root.is_none());
+        assert!(Config::open(&path, true).is_err());
+
+        fs::write(
+            &path,
+            "root = \"library\"\njobs = 3\ntimeout = 5\ncheck_age_restriction = true\n",
+        )?;
+        let config = Config::open(&path, false)?;
+        assert_eq!(
+            config.root.as_deref(),
+            Some(dir.path().join("library").as_path())
+        );
+        let options = config.link_options();
+        assert_eq!(options.jobs, 3);
+        assert_eq!(options.timeout, Duration::from_secs(5));
+        assert_eq!(options.cache_ttl, Duration::from_secs(7 * 24 * 60 * 60));
+        assert!(options.check_age_restriction);
+
+        fs::write(&path, "unknown = 1\n")?;
+        assert!(Config::open(&path, false).is_err());
+        assert!(Config::open(Path::new("missing.toml"), true).is_err());
+        Ok(())
+    }
+}
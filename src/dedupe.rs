@@ -0,0 +1,108 @@
+//! Contains the `dedupe` subcommand, which finds pairs of courses whose IDs are so similar that
+//! they might be the same course created twice.
+
+use std::path::Path;
+
+use anyhow::{bail, Result};
+use ustr::Ustr;
+
+use crate::{
+    exit::{self, Failure, FailureKind},
+    library,
+    output::status,
+};
+
+/// Returns the Levenshtein distance between the two strings, that is, the minimum number of
+/// insertions, deletions, and substitutions of characters needed to turn one into the other.
+fn levenshtein(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<_>>();
+    let mut previous = (0..=b.len()).collect::<Vec<_>>();
+    for (i, a_char) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a_char != *b_char);
+            let deletion = previous[j + 1] + 1;
+            let insertion = current[j] + 1;
+            current.push(substitution.min(deletion).min(insertion));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+/// Returns the last segment of the course ID, which is the part that names the course.
+fn trailing_segment(id: &str) -> &str {
+    id.rsplit("::").next().unwrap_or(id)
+}
+
+/// Returns all the pairs of courses whose IDs have trailing segments at most `threshold` edits
+/// apart, along with the distance between them.
+fn find_near_duplicates(course_ids: &[Ustr], threshold: usize) -> Vec<(Ustr, Ustr, usize)> {
+    let mut pairs = vec![];
+    for (i, a) in course_ids.iter().enumerate() {
+        for b in &course_ids[i + 1..] {
+            let distance = levenshtein(trailing_segment(a), trailing_segment(b));
+            if distance <= threshold {
+                pairs.push((*a, *b, distance));
+            }
+        }
+    }
+    pairs
+}
+
+/// Prints the pairs of courses whose IDs might refer to the same course. If `fail` is true, returns
+/// an error if any pairs are found.
+pub(crate) fn dedupe_courses(root: &Path, threshold: usize, fail: bool) -> Result<()> {
+    let courses_root = library::courses_root(root)?;
+    let course_ids = library::read_all_manifests(&courses_root)?
+        .into_iter()
+        .map(|(_, manifest)| manifest.id)
+        .collect::<Vec<_>>();
+
+    let pairs = find_near_duplicates(&course_ids, threshold);
+    for (a, b, distance) in &pairs {
+        println!("Possible duplicates (distance {distance}): {a} and {b}");
+    }
+    if pairs.is_empty() {
+        status!("No possible duplicate courses found.");
+    } else if fail {
+        bail!(Failure::new(
+            FailureKind::InvalidCourses,
+            format!("found {} possible duplicate courses", pairs.len())
+        ));
+    }
+    exit::fail_on_warnings(
+        FailureKind::InvalidCourses,
+        &[(pairs.len(), "possible duplicate courses")],
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use ustr::Ustr;
+
+    use super::{find_near_duplicates, levenshtein};
+
+    /// Verifies the distance between strings.
+    #[test]
+    fn levenshtein_distance() {
+        assert_eq!(levenshtein("", ""), 0);
+        assert_eq!(levenshtein("blues", ""), 5);
+        assert_eq!(levenshtein("blues_in_f", "blues_f"), 3);
+        assert_eq!(levenshtein("kitten", "sitting"), 3);
+    }
+
+    /// Verifies that only the trailing segments of the IDs are compared.
+    #[test]
+    fn near_duplicates() {
+        let ids = [
+            "trane::transcription::jazz::blues_in_f",
+            "trane::transcription::blues::blues_f",
+            "trane::transcription::jazz::rhythm_changes",
+        ]
+        .map(Ustr::from);
+        assert_eq!(find_near_duplicates(&ids, 3), vec![(ids[0], ids[1], 3)]);
+        assert!(find_near_duplicates(&ids, 2).is_empty());
+    }
+}
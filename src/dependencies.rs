@@ -0,0 +1,133 @@
+//! Contains the `add-dependency` and `remove-dependency` subcommands, which edit the transcription
+//! dependencies of a course without having to edit its manifest by hand.
+
+use std::{collections::BTreeSet, path::Path};
+
+use anyhow::{bail, Result};
+use trane::data::CourseGenerator;
+use ustr::Ustr;
+
+use crate::{
+    changes::FileChanges,
+    exit::{self, FailureKind},
+    graph::DependencyGraph,
+    library,
+    output::status,
+};
+
+/// Removes the repeated dependencies from the list, keeping the first occurrence of each.
+fn dedup_dependencies(dependencies: &mut Vec<Ustr>) {
+    let mut seen = BTreeSet::new();
+    dependencies.retain(|dependency| seen.insert(*dependency));
+}
+
+/// Prints the dependencies of the course after they were changed.
+fn print_dependencies(course_id: Ustr, dependencies: &[Ustr]) {
+    if dependencies.is_empty() {
+        status!("Course {course_id} has no dependencies");
+        return;
+    }
+    status!("Course {course_id} depends on:");
+    for dependency in dependencies {
+        println!("  {dependency}");
+    }
+}
+
+/// Adds `dependency` to the transcription dependencies of `course`. The dependency is refused if it
+/// is the course itself or if it would create a cycle in the dependency graph.
+pub(crate) fn add_dependency(
+    root: &Path,
+    changes: FileChanges,
+    course: &str,
+    dependency: &str,
+) -> Result<()> {
+    let courses_root = library::courses_root(root)?;
+    let (path, mut manifest) = library::read_course(&courses_root, course)?;
+    let course_id = manifest.id;
+    let dependency = library::normalize_course_id(dependency);
+    if dependency == course_id {
+        bail!("course {course_id} cannot depend on itself");
+    }
+
+    // The new dependency creates a cycle if the course is already one of its direct or indirect
+    // dependencies.
+    let manifests = library::read_all_manifests(&courses_root)?;
+    let graph = DependencyGraph::from_manifests(manifests.iter().map(|(_, manifest)| manifest));
+    if graph.reachable_from(dependency).contains(&course_id) {
+        bail!("adding dependency {dependency} to course {course_id} would create a cycle");
+    }
+    if !graph.courses().any(|id| id == dependency) {
+        eprintln!("Warning: dependency {dependency} does not exist");
+        exit::fail_on_warnings(FailureKind::InvalidCourses, &[(1, "missing dependencies")])?;
+    }
+
+    let Some(CourseGenerator::Transcription(config)) = &mut manifest.generator_config else {
+        bail!("course {course_id} is not a transcription course");
+    };
+    if config.transcription_dependencies.contains(&dependency) {
+        status!("Course {course_id} already depends on {dependency}");
+        print_dependencies(course_id, &config.transcription_dependencies);
+        return Ok(());
+    }
+
+    config.transcription_dependencies.push(dependency);
+    dedup_dependencies(&mut config.transcription_dependencies);
+    let dependencies = config.transcription_dependencies.clone();
+    changes.write_manifest(&path, &manifest)?;
+    status!("Added dependency {dependency} to course {course_id}");
+    print_dependencies(course_id, &dependencies);
+    Ok(())
+}
+
+/// Removes `dependency` from the transcription dependencies of `course`. Fails if the course does
+/// not depend on it.
+pub(crate) fn remove_dependency(
+    root: &Path,
+    changes: FileChanges,
+    course: &str,
+    dependency: &str,
+) -> Result<()> {
+    let (path, mut manifest) = library::read_course(&library::courses_root(root)?, course)?;
+    let course_id = manifest.id;
+    let dependency = library::normalize_course_id(dependency);
+    let Some(CourseGenerator::Transcription(config)) = &mut manifest.generator_config else {
+        bail!("course {course_id} is not a transcription course");
+    };
+    if !config.transcription_dependencies.contains(&dependency) {
+        bail!("course {course_id} does not depend on {dependency}");
+    }
+
+    config
+        .transcription_dependencies
+        .retain(|id| *id != dependency);
+    dedup_dependencies(&mut config.transcription_dependencies);
+    let dependencies = config.transcription_dependencies.clone();
+    changes.write_manifest(&path, &manifest)?;
+    status!("Removed dependency {dependency} from course {course_id}");
+    print_dependencies(course_id, &dependencies);
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use ustr::Ustr;
+
+    use super::dedup_dependencies;
+
+    /// Verifies that repeated dependencies are removed while keeping the original order.
+    #[test]
+    fn dedup_keeps_first() {
+        let mut dependencies = ["b", "a", "b", "c", "a"]
+            .into_iter()
+            .map(Ustr::from)
+            .collect::<Vec<_>>();
+        dedup_dependencies(&mut dependencies);
+        assert_eq!(
+            dependencies,
+            ["b", "a", "c"]
+                .into_iter()
+                .map(Ustr::from)
+                .collect::<Vec<_>>()
+        );
+    }
+}
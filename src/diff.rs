@@ -0,0 +1,143 @@
+//! Contains the `diff` subcommand, which compares the courses in this repository with the courses
+//! in another checkout of it, for example one of the main branch.
+
+use std::{collections::BTreeMap, path::Path};
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+use trane::data::CourseManifest;
+use ustr::Ustr;
+
+use crate::{
+    library,
+    output::{self, status, Color},
+    reports::ReportFormat,
+};
+
+/// The differences between the courses in two libraries.
+#[derive(Debug, Default, PartialEq, Eq, Serialize)]
+struct LibraryDiff {
+    /// The IDs of the courses only in this library.
+    added: Vec<Ustr>,
+
+    /// The IDs of the courses only in the other library.
+    removed: Vec<Ustr>,
+
+    /// The IDs of the courses in both libraries whose manifests differ.
+    changed: Vec<Ustr>,
+}
+
+impl LibraryDiff {
+    /// Returns whether the libraries contain the same courses.
+    fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+/// Reads the manifests of all courses in the library at the given root, keyed by course ID.
+fn read_library(root: &Path) -> Result<BTreeMap<Ustr, CourseManifest>> {
+    let manifests = library::read_all_manifests(&library::courses_root(root)?)?;
+    Ok(manifests
+        .into_iter()
+        .map(|(_, manifest)| (manifest.id, manifest))
+        .collect())
+}
+
+/// Compares the courses in this library with those in the other library. Manifests are compared by
+/// their canonical serialization, so differences only in formatting are ignored.
+fn diff_libraries(
+    current: &BTreeMap<Ustr, CourseManifest>,
+    other: &BTreeMap<Ustr, CourseManifest>,
+) -> Result<LibraryDiff> {
+    let mut diff = LibraryDiff::default();
+    for (id, manifest) in current {
+        match other.get(id) {
+            None => diff.added.push(*id),
+            Some(other_manifest) => {
+                if library::serialize_manifest(manifest)?
+                    != library::serialize_manifest(other_manifest)?
+                {
+                    diff.changed.push(*id);
+                }
+            }
+        }
+    }
+    diff.removed = other
+        .keys()
+        .filter(|id| !current.contains_key(*id))
+        .copied()
+        .collect();
+    Ok(diff)
+}
+
+/// Prints the courses added, removed, and changed in the library at `root` relative to the library
+/// at `other_root` in the given format.
+pub(crate) fn diff_courses(root: &Path, other_root: &Path, format: ReportFormat) -> Result<()> {
+    let current = read_library(root)?;
+    let other = read_library(other_root)
+        .with_context(|| format!("failed to read library at {}", other_root.display()))?;
+    let diff = diff_libraries(&current, &other)?;
+
+    match format {
+        ReportFormat::Plain => {
+            for (label, ids, color) in [
+                ("Added", &diff.added, Color::Green),
+                ("Removed", &diff.removed, Color::Red),
+                ("Changed", &diff.changed, Color::Yellow),
+            ] {
+                if ids.is_empty() {
+                    continue;
+                }
+                println!("{}:", output::paint(label, color));
+                for id in ids {
+                    println!("  {id}");
+                }
+            }
+            if diff.is_empty() {
+                status!("Both libraries contain the same courses.");
+            }
+        }
+        ReportFormat::Json => println!("{}", serde_json::to_string_pretty(&diff)?),
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::BTreeMap;
+    use trane::data::{CourseManifest, CourseManifestBuilder};
+    use ustr::Ustr;
+
+    use super::{diff_libraries, LibraryDiff};
+
+    /// Builds a library from a list of course IDs and names.
+    fn build_library(courses: &[(&str, &str)]) -> BTreeMap<Ustr, CourseManifest> {
+        courses
+            .iter()
+            .map(|(id, name)| {
+                let manifest = CourseManifestBuilder::default()
+                    .id(Ustr::from(id))
+                    .name(name.to_string())
+                    .build()
+                    .unwrap();
+                (manifest.id, manifest)
+            })
+            .collect()
+    }
+
+    /// Verifies that added, removed, and changed courses are found.
+    #[test]
+    fn diff_courses() {
+        let current = build_library(&[("a", "A"), ("b", "B"), ("c", "New C")]);
+        let other = build_library(&[("b", "B"), ("c", "C"), ("d", "D")]);
+        assert_eq!(
+            diff_libraries(&current, &other).unwrap(),
+            LibraryDiff {
+                added: vec![Ustr::from("a")],
+                removed: vec![Ustr::from("d")],
+                changed: vec![Ustr::from("c")],
+            }
+        );
+        assert!(diff_libraries(&current, &current).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,185 @@
+//! Contains the `doctor` subcommand, which runs all the checks on the library in sequence and
+//! prints a summary of the issues found by each of them.
+
+use std::path::Path;
+
+use anyhow::{bail, Result};
+
+use crate::{
+    checks::{self, CourseCheck},
+    exit::{self, Failure, FailureKind},
+    library,
+    links::{self, VerifyLinksOptions},
+};
+
+/// The checks enabled in a run of the `doctor` subcommand.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct DoctorChecks {
+    /// Whether to check that trane can open the library.
+    pub library: bool,
+
+    /// Whether to check the dependency graph for cycles and missing dependencies.
+    pub dependencies: bool,
+
+    /// Whether to check that each course is in the directory matching its ID.
+    pub locations: bool,
+
+    /// Whether to check that every course ID contains only the allowed characters.
+    pub ids: bool,
+
+    /// Whether to check that every course lists at least one author.
+    pub authors: bool,
+
+    /// Whether to check for invalid short IDs and short IDs used more than once.
+    pub short_ids: bool,
+
+    /// Whether to check that the passage directories exist and contain files.
+    pub passage_directories: bool,
+
+    /// Whether to check for passages that link to the same video.
+    pub duplicate_links: bool,
+
+    /// Whether to verify the external links.
+    pub links: bool,
+}
+
+/// Runs all the enabled checks, verifying the links with the given options, and prints the number
+/// of issues found by each of them. Returns an error if any check found issues, or if `--strict`
+/// was given and any warnings were found.
+pub(crate) fn run_doctor(
+    root: &Path,
+    checks: &DoctorChecks,
+    link_options: &VerifyLinksOptions,
+) -> Result<()> {
+    let courses_root = library::courses_root(root)?;
+    let manifests_with_paths = library::read_all_manifests(&courses_root)?;
+    let manifests = manifests_with_paths
+        .iter()
+        .map(|(_, manifest)| manifest.clone())
+        .collect::<Vec<_>>();
+
+    // Run the enabled per-course checks on all the courses at once.
+    let course_checks = [
+        (CourseCheck::Location, checks.locations),
+        (CourseCheck::IdFormat, checks.ids),
+        (CourseCheck::Authors, checks.authors),
+        (CourseCheck::ShortIdFormat, checks.short_ids),
+        (CourseCheck::PassageDirectory, checks.passage_directories),
+    ]
+    .into_iter()
+    .filter_map(|(check, enabled)| enabled.then_some(check))
+    .collect::<Vec<_>>();
+    let findings = checks::verify_each_course(&courses_root, &manifests_with_paths, &course_checks);
+    let count = |check| {
+        findings
+            .iter()
+            .filter(|finding| finding.check == check)
+            .count()
+    };
+
+    // Run each check and record the number of issues it found, or `None` if it was skipped.
+    let mut results: Vec<(&str, Option<usize>)> = vec![];
+    let library_error = (checks.library || checks.links)
+        .then(|| library::open_library(root).err())
+        .flatten();
+    let library_issues = checks.library.then(|| match &library_error {
+        None => 0,
+        Some(e) => {
+            println!("Failed to open the library: {e:#}");
+            1
+        }
+    });
+    results.push(("Library", library_issues));
+    results.push((
+        "Dependency graph",
+        checks
+            .dependencies
+            .then(|| checks::verify_dependency_graph(&manifests)),
+    ));
+    results.push((
+        "Course locations",
+        checks.locations.then(|| count(CourseCheck::Location)),
+    ));
+    results.push((
+        "Course IDs",
+        checks.ids.then(|| count(CourseCheck::IdFormat)),
+    ));
+    results.push((
+        "Authors",
+        checks.authors.then(|| count(CourseCheck::Authors)),
+    ));
+    results.push((
+        "Short IDs",
+        checks.short_ids.then(|| {
+            checks::verify_unique_short_ids(&manifests) + count(CourseCheck::ShortIdFormat)
+        }),
+    ));
+    results.push((
+        "Passage directories",
+        checks
+            .passage_directories
+            .then(|| count(CourseCheck::PassageDirectory)),
+    ));
+    results.push((
+        "Duplicate links",
+        checks
+            .duplicate_links
+            .then(|| checks::verify_unique_external_links(&courses_root, &manifests))
+            .transpose()?,
+    ));
+
+    // The links are verified through trane, so they cannot be verified if the library does not
+    // open. Restricted and unreachable links are warnings, as in `verify-links`.
+    let mut invalid_links = 0;
+    let mut link_warnings = (0, 0);
+    let link_issues = match (checks.links, &library_error) {
+        (false, _) => None,
+        (true, Some(_)) => {
+            println!("Could not verify the links because the library failed to open");
+            Some(1)
+        }
+        (true, None) => {
+            let report = links::check_links(root, link_options)?;
+            report.print_failures();
+            let summary = report.summary();
+            invalid_links = summary.invalid;
+            link_warnings = (summary.restricted, summary.unreachable);
+            Some(summary.invalid)
+        }
+    };
+    results.push(("Links", link_issues));
+
+    // Print the summary of all the checks.
+    println!("Summary:");
+    let mut total_issues = 0;
+    for (check, issues) in results {
+        match issues {
+            Some(issues) => {
+                total_issues += issues;
+                println!("  {check}: {issues} issues");
+            }
+            None => println!("  {check}: skipped"),
+        }
+    }
+    let warnings = [
+        (link_warnings.0, "restricted links"),
+        (link_warnings.1, "links that could not be verified"),
+    ];
+    for (count, description) in warnings {
+        if count > 0 {
+            eprintln!("Warning: found {count} {description}");
+        }
+    }
+
+    // The failure is only about the links if the invalid links are the only issues found.
+    let kind = if total_issues == invalid_links {
+        FailureKind::InvalidLinks
+    } else {
+        FailureKind::InvalidCourses
+    };
+    if total_issues > 0 {
+        bail!(Failure::new(kind, format!("found {total_issues} issues")));
+    }
+    exit::fail_on_warnings(FailureKind::InvalidLinks, &warnings)?;
+    Ok(())
+}
@@ -0,0 +1,160 @@
+//! Contains the exit codes of the process, which tell scripts and CI what kind of failure happened
+//! without having to parse the output. Errors are tagged with their kind by wrapping them in a
+//! `Failure`, and untagged errors result in the generic exit code. Also contains the global
+//! `--strict` setting, which turns the advisory warnings of every command into failures.
+
+use std::{
+    error::Error,
+    fmt,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use anyhow::{bail, Result};
+
+/// Whether warnings are promoted to errors.
+static STRICT: AtomicBool = AtomicBool::new(false);
+
+/// The exit codes documented in the help of the command.
+pub(crate) const EXIT_CODES_HELP: &str = "Exit codes:
+  0  Success
+  1  Any other error
+  2  Invalid links were found
+  3  Invalid courses were found
+  4  The command line arguments are invalid";
+
+/// The kind of a failure, which determines the exit code of the process.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum FailureKind {
+    /// Some links do not refer to valid resources.
+    InvalidLinks,
+
+    /// Some courses failed verification.
+    InvalidCourses,
+
+    /// The command line arguments are invalid.
+    Usage,
+}
+
+impl FailureKind {
+    /// Returns the exit code of the process for this kind of failure.
+    pub fn exit_code(self) -> i32 {
+        match self {
+            FailureKind::InvalidLinks => 2,
+            FailureKind::InvalidCourses => 3,
+            FailureKind::Usage => 4,
+        }
+    }
+}
+
+/// An error tagged with the kind of failure. The message is the one shown to the user.
+#[derive(Debug)]
+pub(crate) struct Failure {
+    /// The kind of failure.
+    kind: FailureKind,
+
+    /// The description of the failure.
+    message: String,
+}
+
+impl Failure {
+    /// Creates a failure of the given kind with the given message.
+    pub fn new(kind: FailureKind, message: impl Into<String>) -> Self {
+        Self {
+            kind,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for Failure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl Error for Failure {}
+
+/// Sets whether warnings are promoted to errors.
+pub(crate) fn set_strict(strict: bool) {
+    STRICT.store(strict, Ordering::Relaxed);
+}
+
+/// Returns whether warnings are promoted to errors.
+pub(crate) fn is_strict() -> bool {
+    STRICT.load(Ordering::Relaxed)
+}
+
+/// Returns the message of the failure for the given warnings promoted to errors, or `None` if there
+/// are no warnings. Each warning is given as its count and a description of the finding.
+fn promoted_message(warnings: &[(usize, &str)]) -> Option<String> {
+    let found = warnings
+        .iter()
+        .filter(|(count, _)| *count > 0)
+        .map(|(count, description)| format!("{count} {description}"))
+        .collect::<Vec<_>>();
+    if found.is_empty() {
+        return None;
+    }
+    Some(format!(
+        "found {}, promoted to errors by --strict",
+        found.join(" and ")
+    ))
+}
+
+/// Returns a failure of the given kind if `--strict` was given and any of the warnings were found.
+/// Each warning is given as its count and a description of the finding, so the message lists which
+/// findings were promoted.
+pub(crate) fn fail_on_warnings(kind: FailureKind, warnings: &[(usize, &str)]) -> Result<()> {
+    if !is_strict() {
+        return Ok(());
+    }
+    if let Some(message) = promoted_message(warnings) {
+        bail!(Failure::new(kind, message));
+    }
+    Ok(())
+}
+
+/// Returns the exit code for the error, which is the code of the first failure in its chain of
+/// causes or 1 if none of the causes is tagged.
+pub(crate) fn exit_code(error: &anyhow::Error) -> i32 {
+    error
+        .chain()
+        .find_map(|cause| cause.downcast_ref::<Failure>())
+        .map_or(1, |failure| failure.kind.exit_code())
+}
+
+#[cfg(test)]
+mod test {
+    use anyhow::{anyhow, Context, Result};
+
+    use super::{exit_code, promoted_message, Failure, FailureKind};
+
+    /// Verifies that the exit code comes from the tagged cause, even when context was added.
+    #[test]
+    fn exit_codes() {
+        let result: Result<()> =
+            Err(Failure::new(FailureKind::InvalidCourses, "found 1 issue").into());
+        let error = result.context("error validating courses").unwrap_err();
+        assert_eq!(exit_code(&error), 3);
+        assert_eq!(error.to_string(), "error validating courses");
+        assert_eq!(exit_code(&anyhow!("failed to read file")), 1);
+    }
+
+    /// Verifies that the message lists only the warnings that were found.
+    #[test]
+    fn promoted_warnings() {
+        assert_eq!(promoted_message(&[(0, "lint warnings")]), None);
+        assert_eq!(
+            promoted_message(&[
+                (2, "restricted links"),
+                (0, "invalid links"),
+                (1, "links that timed out")
+            ])
+            .as_deref(),
+            Some(
+                "found 2 restricted links and 1 links that timed out, promoted to errors by \
+                --strict"
+            )
+        );
+    }
+}
@@ -0,0 +1,131 @@
+//! Contains the `fix` subcommand, which repairs the issues in the course manifests that can be
+//! fixed without human judgment. Issues that need a decision, like dependencies on missing courses,
+//! are left to be fixed by hand.
+
+use std::{collections::BTreeMap, fs, path::Path};
+
+use anyhow::{Context, Result};
+use trane::data::{CourseGenerator, CourseManifest};
+
+use crate::{changes::FileChanges, library, output::status, passages};
+
+/// The author every course in this repository must list.
+const TRANE_PROJECT_AUTHOR: &str = "The Trane Project";
+
+/// The course series given to the courses that do not set one.
+const DEFAULT_COURSE_SERIES: &str = "trane_transcription";
+
+/// Repairs the fields of the manifest that can be fixed automatically and returns a description of
+/// each repair.
+fn fix_manifest(manifest: &mut CourseManifest) -> Vec<String> {
+    let mut fixes = vec![];
+    let authors = manifest.authors.get_or_insert_with(Vec::new);
+    if !authors.iter().any(|author| author == TRANE_PROJECT_AUTHOR) {
+        authors.push(TRANE_PROJECT_AUTHOR.to_string());
+        fixes.push(format!("added {TRANE_PROJECT_AUTHOR} to the authors"));
+    }
+
+    let metadata = manifest.metadata.get_or_insert_with(BTreeMap::new);
+    if !metadata.contains_key("course_series") {
+        metadata.insert(
+            "course_series".to_string(),
+            vec![DEFAULT_COURSE_SERIES.to_string()],
+        );
+        fixes.push("added the course_series metadata".to_string());
+    }
+    fixes
+}
+
+/// Repairs the missing authors and course series of every course, rewrites the manifests that do
+/// not use the canonical formatting, and moves the courses that are not in the directory derived
+/// from their IDs. If `sort_passages` is true, the inlined passages are also sorted by short ID.
+pub(crate) fn fix_courses(root: &Path, changes: FileChanges, sort_passages: bool) -> Result<()> {
+    let courses_root = library::courses_root(root)?;
+    let manifest_paths = library::find_manifests(&courses_root)?;
+    let mut fixed = 0;
+    for path in &manifest_paths {
+        let contents = fs::read(path)
+            .with_context(|| format!("failed to read course manifest at {}", path.display()))?;
+        let mut manifest = library::read_manifest(path)?;
+        let mut fixes = fix_manifest(&mut manifest);
+        if let (true, Some(CourseGenerator::Transcription(config))) =
+            (sort_passages, &mut manifest.generator_config)
+        {
+            if passages::sort_inlined_passages(config) {
+                fixes.push("sorted the inlined passages by short ID".to_string());
+            }
+        }
+        if fixes.is_empty() && library::serialize_manifest(&manifest)? != contents {
+            fixes.push("reformatted the manifest".to_string());
+        }
+        if !fixes.is_empty() {
+            changes.write_manifest(path, &manifest)?;
+        }
+
+        // Move the course to the directory matching its ID, unless that would also move other
+        // courses or replace an existing directory.
+        let expected_path = library::manifest_path(&courses_root, &manifest.id);
+        if !manifest.id.is_empty() && expected_path != *path {
+            let directory = path.parent().unwrap_or(Path::new("."));
+            let expected_directory = library::course_directory(&courses_root, &manifest.id);
+            let has_nested_courses = manifest_paths
+                .iter()
+                .any(|other| other != path && other.starts_with(directory));
+            if expected_directory.exists() || has_nested_courses {
+                println!(
+                    "Course {}: not moving {} to {} because the move is ambiguous",
+                    manifest.id,
+                    directory.display(),
+                    expected_directory.display()
+                );
+            } else {
+                if let Some(parent) = expected_directory.parent() {
+                    changes.create_dir_all(parent)?;
+                }
+                changes.rename(directory, &expected_directory)?;
+                fixes.push(format!(
+                    "moved the course from {} to {}",
+                    directory.display(),
+                    expected_directory.display()
+                ));
+            }
+        }
+
+        for fix in &fixes {
+            println!("Course {}: {fix}", manifest.id);
+        }
+        fixed += fixes.len();
+    }
+    status!("Fixed {fixed} issues.");
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use trane::data::CourseManifestBuilder;
+    use ustr::Ustr;
+
+    use super::fix_manifest;
+
+    /// Verifies that the missing author and course series are added, and that a second pass finds
+    /// nothing to fix.
+    #[test]
+    fn fix_missing_fields() {
+        let mut manifest = CourseManifestBuilder::default()
+            .id(Ustr::from("trane::transcription::a"))
+            .name("".to_string())
+            .authors(Some(vec!["Someone".to_string()]))
+            .build()
+            .unwrap();
+        assert_eq!(fix_manifest(&mut manifest).len(), 2);
+        assert_eq!(
+            manifest.authors,
+            Some(vec!["Someone".to_string(), "The Trane Project".to_string()])
+        );
+        assert_eq!(
+            manifest.metadata.as_ref().unwrap()["course_series"],
+            vec!["trane_transcription".to_string()]
+        );
+        assert!(fix_manifest(&mut manifest).is_empty());
+    }
+}
@@ -0,0 +1,142 @@
+//! Contains the utilities to find the courses changed in the git repository, used to restrict the
+//! verification to the courses affected by a change.
+
+use std::{path::Path, process::Command};
+
+use anyhow::{bail, Result};
+use ustr::Ustr;
+
+use crate::{
+    exit::{Failure, FailureKind},
+    graph::DependencyGraph,
+    library::{self, ADDITIONAL_LINKS_FILE, MANIFEST_FILE},
+};
+
+/// Runs git in the root with the given arguments and returns the lines of its output, or fails with
+/// the given description of the command if git fails.
+fn git_lines(root: &Path, args: &[&str], description: &str) -> Result<Vec<String>> {
+    let output = Command::new("git").args(args).current_dir(root).output()?;
+    if !output.status.success() {
+        bail!(
+            "failed to {description}: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(str::to_string)
+        .collect())
+}
+
+/// Returns the paths, relative to the root, of the files changed since the given git ref, including
+/// the untracked files not ignored by git, or `None` if the root is not in a git repository.
+fn changed_files(root: &Path, since: &str) -> Result<Option<Vec<String>>> {
+    // A ref starting with a dash would be parsed by git as an option.
+    if since.starts_with('-') {
+        bail!(Failure::new(
+            FailureKind::Usage,
+            format!("invalid git ref {since}")
+        ));
+    }
+
+    let is_repository = Command::new("git")
+        .args(["rev-parse", "--is-inside-work-tree"])
+        .current_dir(root)
+        .output()
+        .is_ok_and(|output| output.status.success());
+    if !is_repository {
+        return Ok(None);
+    }
+
+    // `git diff` only lists the files git tracks, so new courses that were not added yet are listed
+    // separately.
+    let mut files = git_lines(
+        root,
+        &["diff", "--name-only", "--relative", since, "--"],
+        &format!("find the files changed since {since}"),
+    )?;
+    files.extend(git_lines(
+        root,
+        &["ls-files", "--others", "--exclude-standard"],
+        "find the untracked files",
+    )?);
+    files.sort();
+    files.dedup();
+    Ok(Some(files))
+}
+
+/// Returns the IDs of the courses whose manifests changed since the given git ref, along with all
+/// the courses that depend on them directly or indirectly. Returns `None` if the root is not in a
+/// git repository, in which case all the courses should be verified.
+pub(crate) fn changed_courses(root: &Path, since: &str) -> Result<Option<Vec<Ustr>>> {
+    let Some(files) = changed_files(root, since)? else {
+        return Ok(None);
+    };
+
+    // Read the IDs of the changed courses. A change to the additional links of a course is a change
+    // to the course. Deleted manifests are ignored.
+    let manifests = library::read_all_manifests(&library::courses_root(root)?)?;
+    let changed = files
+        .iter()
+        .filter(|file| file.ends_with(MANIFEST_FILE) || file.ends_with(ADDITIONAL_LINKS_FILE))
+        .filter_map(|file| {
+            let path = root.join(file).with_file_name(MANIFEST_FILE);
+            manifests
+                .iter()
+                .find(|(manifest_path, _)| *manifest_path == path)
+                .map(|(_, manifest)| manifest.id)
+        })
+        .collect::<Vec<_>>();
+
+    let graph = DependencyGraph::from_manifests(manifests.iter().map(|(_, manifest)| manifest));
+    Ok(Some(
+        graph.dependents_closure(&changed).into_iter().collect(),
+    ))
+}
+
+#[cfg(test)]
+mod test {
+    use anyhow::{ensure, Result};
+    use std::{fs, path::Path, process::Command};
+
+    use super::changed_files;
+
+    /// Runs git in the directory with the given arguments and fails if git fails.
+    fn git(directory: &Path, args: &[&str]) -> Result<()> {
+        let status = Command::new("git")
+            .args(["-c", "user.name=test", "-c", "user.email=test@example.com"])
+            .args(args)
+            .current_dir(directory)
+            .output()?
+            .status;
+        ensure!(status.success(), "git {} failed", args.join(" "));
+        Ok(())
+    }
+
+    /// Verifies that the changed files include the modified tracked files and the untracked files,
+    /// but not the unchanged or ignored files.
+    #[test]
+    fn changed_and_untracked_files() -> Result<()> {
+        let directory = tempfile::tempdir()?;
+        let root = directory.path();
+        git(root, &["init", "--quiet"])?;
+        fs::write(root.join(".gitignore"), "ignored.json\n")?;
+        fs::write(root.join("changed.json"), "{}")?;
+        fs::write(root.join("unchanged.json"), "{}")?;
+        git(root, &["add", "."])?;
+        git(root, &["commit", "--quiet", "-m", "initial"])?;
+
+        fs::write(root.join("changed.json"), "[]")?;
+        fs::write(root.join("ignored.json"), "{}")?;
+        fs::create_dir(root.join("new"))?;
+        fs::write(root.join("new").join("course_manifest.json"), "{}")?;
+        assert_eq!(
+            changed_files(root, "HEAD")?,
+            Some(vec![
+                "changed.json".to_string(),
+                "new/course_manifest.json".to_string()
+            ])
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,409 @@
+//! Contains the graph of dependencies among the transcription courses, built from the
+//! `transcription_dependencies` field of each course.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use clap::ValueEnum;
+use trane::data::CourseManifest;
+use ustr::Ustr;
+
+use crate::library;
+
+/// The state of a course during the depth-first search used to find cycles.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum VisitState {
+    /// The course is in the current path of the search.
+    InProgress,
+
+    /// The course and all its dependencies have been visited.
+    Done,
+}
+
+/// The format used to print the dependency graph.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub(crate) enum GraphFormat {
+    /// A Graphviz DOT document.
+    #[default]
+    Dot,
+
+    /// A Mermaid flowchart.
+    Mermaid,
+}
+
+/// A directed graph in which each course points to its transcription dependencies.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct DependencyGraph {
+    /// A mapping of course ID to the IDs of its dependencies.
+    dependencies: BTreeMap<Ustr, Vec<Ustr>>,
+}
+
+impl DependencyGraph {
+    /// Creates a graph from a mapping of course ID to the IDs of its dependencies.
+    pub fn new(dependencies: BTreeMap<Ustr, Vec<Ustr>>) -> Self {
+        Self { dependencies }
+    }
+
+    /// Creates a graph from the given manifests. Every course is a node in the graph, but only
+    /// transcription courses have outgoing edges.
+    pub fn from_manifests<'a>(manifests: impl IntoIterator<Item = &'a CourseManifest>) -> Self {
+        let dependencies = manifests
+            .into_iter()
+            .map(|manifest| {
+                let dependencies = library::transcription_config(manifest)
+                    .map(|config| config.transcription_dependencies.clone())
+                    .unwrap_or_default();
+                (manifest.id, dependencies)
+            })
+            .collect();
+        Self::new(dependencies)
+    }
+
+    /// Returns the IDs of all the courses in the graph, sorted alphabetically.
+    pub fn courses(&self) -> impl Iterator<Item = Ustr> + '_ {
+        self.dependencies.keys().copied()
+    }
+
+    /// Returns the dependencies of the given course.
+    pub fn dependencies(&self, course_id: Ustr) -> &[Ustr] {
+        self.dependencies
+            .get(&course_id)
+            .map_or(&[], |dependencies| dependencies.as_slice())
+    }
+
+    /// Visits the course and its dependencies, recording every cycle found along the way.
+    fn visit(
+        &self,
+        course_id: Ustr,
+        path: &mut Vec<Ustr>,
+        states: &mut BTreeMap<Ustr, VisitState>,
+        cycles: &mut Vec<Vec<Ustr>>,
+    ) {
+        states.insert(course_id, VisitState::InProgress);
+        path.push(course_id);
+        for dependency in self.dependencies(course_id) {
+            match states.get(dependency) {
+                // The dependency is already in the current path, so the part of the path starting
+                // at the dependency forms a cycle.
+                Some(VisitState::InProgress) => {
+                    let start = path.iter().position(|id| id == dependency).unwrap_or(0);
+                    let mut cycle = path[start..].to_vec();
+                    cycle.push(*dependency);
+                    cycles.push(cycle);
+                }
+                Some(VisitState::Done) => {}
+                None => {
+                    // Dependencies on missing courses are reported separately.
+                    if self.dependencies.contains_key(dependency) {
+                        self.visit(*dependency, path, states, cycles);
+                    }
+                }
+            }
+        }
+        path.pop();
+        states.insert(course_id, VisitState::Done);
+    }
+
+    /// Returns the cycles in the graph. Each cycle is a list of course IDs in which each course
+    /// depends on the next and the last course is the same as the first.
+    pub fn find_cycles(&self) -> Vec<Vec<Ustr>> {
+        let mut states = BTreeMap::new();
+        let mut cycles = vec![];
+        for course_id in self.courses() {
+            if !states.contains_key(&course_id) {
+                self.visit(course_id, &mut vec![], &mut states, &mut cycles);
+            }
+        }
+        cycles
+    }
+
+    /// Returns all the pairs `(course_id, dependency)` in which the dependency is not a course in
+    /// the graph.
+    pub fn find_dangling_dependencies(&self) -> Vec<(Ustr, Ustr)> {
+        let courses = self.courses().collect::<BTreeSet<_>>();
+        self.dependencies
+            .iter()
+            .flat_map(|(course_id, dependencies)| {
+                dependencies
+                    .iter()
+                    .filter(|dependency| !courses.contains(*dependency))
+                    .map(|dependency| (*course_id, *dependency))
+            })
+            .collect()
+    }
+
+    /// Returns the given courses along with every course that depends on any of them, directly or
+    /// indirectly.
+    pub fn dependents_closure(&self, course_ids: &[Ustr]) -> BTreeSet<Ustr> {
+        let mut closure = course_ids.iter().copied().collect::<BTreeSet<_>>();
+        let mut pending = course_ids.to_vec();
+        while let Some(course_id) = pending.pop() {
+            for (dependency, dependent) in self.edges() {
+                if dependency == course_id && closure.insert(dependent) {
+                    pending.push(dependent);
+                }
+            }
+        }
+        closure
+    }
+
+    /// Returns whether the course is isolated, that is, whether it has no dependencies and no other
+    /// course depends on it.
+    pub fn is_isolated(&self, course_id: Ustr) -> bool {
+        self.dependencies(course_id).is_empty()
+            && !self.edges().any(|(dependency, _)| dependency == course_id)
+    }
+
+    /// Returns the length, counted in edges, of the longest chain of dependencies starting at the
+    /// course. `depths` memoizes the depths already computed, and `path` holds the courses in the
+    /// current chain, so that the edges closing a cycle are not followed.
+    fn depth(
+        &self,
+        course_id: Ustr,
+        depths: &mut BTreeMap<Ustr, usize>,
+        path: &mut BTreeSet<Ustr>,
+    ) -> usize {
+        if let Some(depth) = depths.get(&course_id) {
+            return *depth;
+        }
+        path.insert(course_id);
+        let mut depth = 0;
+        for dependency in self.dependencies(course_id) {
+            // Dependencies on missing courses are reported separately.
+            if self.dependencies.contains_key(dependency) && !path.contains(dependency) {
+                depth = depth.max(self.depth(*dependency, depths, path) + 1);
+            }
+        }
+        path.remove(&course_id);
+        depths.insert(course_id, depth);
+        depth
+    }
+
+    /// Returns the length, counted in edges, of the longest chain of dependencies in the graph.
+    pub fn max_depth(&self) -> usize {
+        let mut depths = BTreeMap::new();
+        self.courses()
+            .map(|course_id| self.depth(course_id, &mut depths, &mut BTreeSet::new()))
+            .max()
+            .unwrap_or_default()
+    }
+
+    /// Returns the graph with every edge inverted, in which each course points to the courses that
+    /// depend on it. Dependencies on courses not in the graph are dropped.
+    pub fn reversed(&self) -> Self {
+        let mut dependents = self
+            .courses()
+            .map(|course_id| (course_id, vec![]))
+            .collect::<BTreeMap<_, _>>();
+        for (dependency, dependent) in self.edges() {
+            if let Some(course_dependents) = dependents.get_mut(&dependency) {
+                course_dependents.push(dependent);
+            }
+        }
+        Self::new(dependents)
+    }
+
+    /// Returns the courses reachable from the given course by following the edges of the graph,
+    /// not including the course itself. In the original graph, these are all the direct and
+    /// indirect dependencies of the course, and in the reversed graph, all its dependents.
+    pub fn reachable_from(&self, course_id: Ustr) -> BTreeSet<Ustr> {
+        let mut reachable = BTreeSet::new();
+        let mut pending = vec![course_id];
+        while let Some(current) = pending.pop() {
+            for next in self.dependencies(current) {
+                if *next != course_id && reachable.insert(*next) {
+                    pending.push(*next);
+                }
+            }
+        }
+        reachable
+    }
+
+    /// Returns all the edges `(dependency, dependent)` in the graph, that is, the edges point from
+    /// each dependency to the courses that depend on it.
+    fn edges(&self) -> impl Iterator<Item = (Ustr, Ustr)> + '_ {
+        self.dependencies
+            .iter()
+            .flat_map(|(course_id, dependencies)| {
+                dependencies
+                    .iter()
+                    .map(|dependency| (*dependency, *course_id))
+            })
+    }
+
+    /// Returns the graph as a Graphviz DOT document.
+    pub fn to_dot(&self) -> String {
+        let mut dot = "digraph dependencies {\n".to_string();
+        for course_id in self.courses() {
+            dot.push_str(&format!("    \"{course_id}\";\n"));
+        }
+        for (dependency, dependent) in self.edges() {
+            dot.push_str(&format!("    \"{dependency}\" -> \"{dependent}\";\n"));
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// Returns the graph as a Mermaid flowchart. Mermaid does not allow colons in node IDs, so each
+    /// course is given a numeric node ID and labeled with its course ID.
+    pub fn to_mermaid(&self) -> String {
+        let mut nodes = BTreeMap::new();
+        let mut mermaid = "graph LR\n".to_string();
+        let nodes_needed = self
+            .courses()
+            .chain(self.edges().map(|(dependency, _)| dependency));
+        for course_id in nodes_needed {
+            if !nodes.contains_key(&course_id) {
+                let node = format!("n{}", nodes.len());
+                mermaid.push_str(&format!("    {node}[\"{course_id}\"]\n"));
+                nodes.insert(course_id, node);
+            }
+        }
+        for (dependency, dependent) in self.edges() {
+            mermaid.push_str(&format!(
+                "    {} --> {}\n",
+                nodes[&dependency], nodes[&dependent]
+            ));
+        }
+        mermaid
+    }
+
+    /// Returns the graph in the given format.
+    pub fn render(&self, format: GraphFormat) -> String {
+        match format {
+            GraphFormat::Dot => self.to_dot(),
+            GraphFormat::Mermaid => self.to_mermaid(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::BTreeMap;
+    use ustr::Ustr;
+
+    use super::DependencyGraph;
+
+    /// Builds a graph from a list of courses and their dependencies.
+    fn build_graph(edges: &[(&str, &[&str])]) -> DependencyGraph {
+        DependencyGraph::new(
+            edges
+                .iter()
+                .map(|(id, dependencies)| {
+                    (
+                        Ustr::from(id),
+                        dependencies.iter().map(|d| Ustr::from(d)).collect(),
+                    )
+                })
+                .collect::<BTreeMap<_, _>>(),
+        )
+    }
+
+    /// Verifies that cycles in the graph are found and reported in order.
+    #[test]
+    fn find_cycles() {
+        let graph = build_graph(&[("a", &["b"]), ("b", &["c"]), ("c", &["a"]), ("d", &["a"])]);
+        let cycles = graph.find_cycles();
+        assert_eq!(
+            cycles,
+            vec![vec![
+                Ustr::from("a"),
+                Ustr::from("b"),
+                Ustr::from("c"),
+                Ustr::from("a")
+            ]]
+        );
+
+        let graph = build_graph(&[("a", &["b"]), ("b", &[]), ("c", &["a", "b"])]);
+        assert!(graph.find_cycles().is_empty());
+    }
+
+    /// Verifies that dependencies on courses not in the graph are found.
+    #[test]
+    fn find_dangling_dependencies() {
+        let graph = build_graph(&[("a", &["b", "missing"]), ("b", &[])]);
+        assert_eq!(
+            graph.find_dangling_dependencies(),
+            vec![(Ustr::from("a"), Ustr::from("missing"))]
+        );
+    }
+
+    /// Verifies that the closure includes the direct and indirect dependents of the courses.
+    #[test]
+    fn dependents_closure() {
+        let graph = build_graph(&[("a", &[]), ("b", &["a"]), ("c", &["b"]), ("d", &[])]);
+        assert_eq!(
+            graph.dependents_closure(&[Ustr::from("a")]),
+            ["a", "b", "c"].into_iter().map(Ustr::from).collect()
+        );
+        assert_eq!(
+            graph.dependents_closure(&[Ustr::from("d")]),
+            [Ustr::from("d")].into_iter().collect()
+        );
+    }
+
+    /// Verifies that only the courses without dependencies and dependents are isolated.
+    #[test]
+    fn is_isolated() {
+        let graph = build_graph(&[("a", &[]), ("b", &["a"]), ("c", &[]), ("d", &["missing"])]);
+        assert!(!graph.is_isolated(Ustr::from("a")));
+        assert!(!graph.is_isolated(Ustr::from("b")));
+        assert!(graph.is_isolated(Ustr::from("c")));
+        assert!(!graph.is_isolated(Ustr::from("d")));
+    }
+
+    /// Verifies that the longest chain of dependencies is found, even if the graph has cycles.
+    #[test]
+    fn max_depth() {
+        let graph = build_graph(&[("a", &[]), ("b", &["a"]), ("c", &["b", "a"]), ("d", &[])]);
+        assert_eq!(graph.max_depth(), 2);
+
+        let graph = build_graph(&[("a", &["b"]), ("b", &["a"]), ("c", &["missing"])]);
+        assert_eq!(graph.max_depth(), 1);
+        assert_eq!(DependencyGraph::default().max_depth(), 0);
+    }
+
+    /// Verifies that reversing the graph makes each course point to its dependents, and that the
+    /// reachable courses follow the direction of the edges.
+    #[test]
+    fn reachable_courses() {
+        let graph = build_graph(&[
+            ("a", &[]),
+            ("b", &["a"]),
+            ("c", &["b", "missing"]),
+            ("d", &[]),
+        ]);
+        assert_eq!(
+            graph.reachable_from(Ustr::from("c")),
+            ["a", "b", "missing"].into_iter().map(Ustr::from).collect()
+        );
+        assert!(graph.reachable_from(Ustr::from("d")).is_empty());
+
+        let reversed = graph.reversed();
+        assert_eq!(reversed.dependencies(Ustr::from("a")), &[Ustr::from("b")]);
+        assert_eq!(
+            reversed.reachable_from(Ustr::from("a")),
+            ["b", "c"].into_iter().map(Ustr::from).collect()
+        );
+    }
+
+    /// Verifies that the graph is rendered as DOT with edges from dependencies to dependents.
+    #[test]
+    fn to_dot() {
+        let graph = build_graph(&[("a", &[]), ("b", &["a"])]);
+        assert_eq!(
+            graph.to_dot(),
+            "digraph dependencies {\n    \"a\";\n    \"b\";\n    \"a\" -> \"b\";\n}\n"
+        );
+    }
+
+    /// Verifies that the graph is rendered as Mermaid, including dependencies on missing courses.
+    #[test]
+    fn to_mermaid() {
+        let graph = build_graph(&[("a::b", &["missing"]), ("c", &["a::b"])]);
+        assert_eq!(
+            graph.to_mermaid(),
+            "graph LR\n    n0[\"a::b\"]\n    n1[\"c\"]\n    n2[\"missing\"]\n    \
+            n2 --> n0\n    n0 --> n1\n"
+        );
+    }
+}
@@ -0,0 +1,83 @@
+//! Contains the optional ignore file, which lists the courses skipped by the verification and lint
+//! commands, for example because they are still a work in progress.
+
+use std::{fs, path::Path};
+
+use anyhow::{Context, Result};
+use ustr::Ustr;
+
+use crate::{library, output::status};
+
+/// The name of the ignore file looked up in the root of the repository.
+pub(crate) const IGNORE_FILE: &str = ".trane-transcription-ignore";
+
+/// Parses the contents of an ignore file. Each non-empty line is a course ID or glob pattern, with
+/// or without the `trane::transcription::` prefix, and lines starting with `#` are comments.
+fn parse_ignore_file(contents: &str) -> Vec<Ustr> {
+    contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(library::normalize_course_id)
+        .collect()
+}
+
+/// Returns the patterns of the courses to skip listed in the ignore file at the root of the
+/// repository, or an empty list if the file does not exist or `no_ignore` is true.
+pub(crate) fn ignored_courses(root: &Path, no_ignore: bool) -> Result<Vec<Ustr>> {
+    let path = root.join(IGNORE_FILE);
+    if no_ignore || !path.exists() {
+        return Ok(vec![]);
+    }
+    let contents = fs::read_to_string(&path)
+        .with_context(|| format!("failed to read ignore file at {}", path.display()))?;
+    Ok(parse_ignore_file(&contents))
+}
+
+/// Returns whether the course matches any of the patterns in the ignore file.
+pub(crate) fn is_ignored(ignored: &[Ustr], course_id: Ustr) -> bool {
+    library::is_excluded(ignored, course_id)
+}
+
+/// Prints that each of the given courses was skipped because it matches the ignore file.
+pub(crate) fn report_ignored(course_ids: &[Ustr]) {
+    for course_id in course_ids {
+        status!("Course {course_id}: skipped (ignored)");
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ustr::Ustr;
+
+    use super::{is_ignored, parse_ignore_file};
+
+    /// Verifies that the ignore file is parsed and its patterns matched against course IDs.
+    #[test]
+    fn ignore_courses() {
+        let ignored = parse_ignore_file("# Work in progress\n\njazz::*::songs\n  folk::wip  \n");
+        assert_eq!(
+            ignored,
+            vec![
+                Ustr::from("trane::transcription::jazz::*::songs"),
+                Ustr::from("trane::transcription::folk::wip"),
+            ]
+        );
+        assert!(is_ignored(
+            &ignored,
+            Ustr::from("trane::transcription::jazz::miles_davis::songs")
+        ));
+        assert!(is_ignored(
+            &ignored,
+            Ustr::from("trane::transcription::folk::wip")
+        ));
+        assert!(!is_ignored(
+            &ignored,
+            Ustr::from("trane::transcription::folk::adrianne_lenker::songs")
+        ));
+        assert!(!is_ignored(
+            &[],
+            Ustr::from("trane::transcription::folk::wip")
+        ));
+    }
+}
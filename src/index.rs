@@ -0,0 +1,65 @@
+//! Contains the `reindex` subcommand, which generates an index of all the courses at the root of
+//! the repository so that they can be listed without reading every manifest.
+
+use std::{fs, path::Path};
+
+use anyhow::{bail, Result};
+use serde::Serialize;
+use ustr::Ustr;
+
+use crate::{changes::FileChanges, library, output::status};
+
+/// The name of the index file, relative to the root of the repository.
+pub(crate) const INDEX_FILE: &str = "library_index.json";
+
+/// A course in the library index.
+#[derive(Debug, Serialize)]
+struct IndexEntry {
+    /// The ID of the course.
+    id: Ustr,
+
+    /// The name of the course.
+    name: String,
+
+    /// The number of inlined passages in the course.
+    passage_count: usize,
+
+    /// The IDs of the transcription dependencies of the course.
+    dependencies: Vec<Ustr>,
+}
+
+/// Regenerates the library index from the transcription courses. If `check` is true, the index is
+/// not written, and an error is returned if the existing index differs from the generated one.
+pub(crate) fn reindex(root: &Path, changes: FileChanges, check: bool) -> Result<()> {
+    // Open the trane-transcription library in trane.
+    let trane = library::open_library(root)?;
+    let entries = library::transcription_courses(&trane)
+        .into_iter()
+        .map(|(manifest, config)| IndexEntry {
+            id: manifest.id,
+            name: manifest.name,
+            passage_count: config.inlined_passages.len(),
+            dependencies: config.transcription_dependencies,
+        })
+        .collect::<Vec<_>>();
+    let buf = library::to_pretty_json(&entries)?;
+
+    let path = root.join(INDEX_FILE);
+    if check {
+        if fs::read(&path).ok().as_deref() != Some(buf.as_slice()) {
+            bail!(
+                "library index at {} is out of date, run reindex to update it",
+                path.display()
+            );
+        }
+        status!("The library index is up to date.");
+        return Ok(());
+    }
+    changes.write_file(&path, &buf)?;
+    status!(
+        "Wrote the index of {} courses to {}",
+        entries.len(),
+        path.display()
+    );
+    Ok(())
+}
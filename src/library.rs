@@ -0,0 +1,591 @@
+//! Contains utilities to locate, read, and write the course manifests stored in this repository.
+
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{bail, Context, Result};
+use serde::ser::Serialize;
+use trane::{
+    course_library::CourseLibrary,
+    data::{
+        course_generator::transcription::{
+            TranscriptionAsset, TranscriptionConfig, TranscriptionLink, TranscriptionPassages,
+        },
+        CourseGenerator, CourseManifest,
+    },
+    Trane,
+};
+use ustr::Ustr;
+use walkdir::WalkDir;
+
+use crate::links::ExternalLink;
+
+/// The prefix shared by the IDs of all the courses in this repository.
+pub(crate) const COURSE_ID_PREFIX: &str = "trane::transcription::";
+
+/// The name of the directory, relative to the root of the repository, containing the courses.
+pub(crate) const COURSES_DIRECTORY: &str = "courses";
+
+/// The name of the file, in the directory of each course, listing the additional external links of
+/// its passages.
+pub(crate) const ADDITIONAL_LINKS_FILE: &str = "additional_links.json";
+
+/// The name of the file containing the manifest of a course.
+pub(crate) const MANIFEST_FILE: &str = "course_manifest.json";
+
+/// The additional external links of the passages of a course, keyed by the short ID of each
+/// passage. trane only allows a single external link per track, so the links to the same recording
+/// on other platforms are stored in a file next to the manifest, which trane does not read.
+pub(crate) type AdditionalLinks = BTreeMap<String, Vec<String>>;
+
+/// Returns the fully-qualified ID of the course, adding the `trane::transcription::` prefix if it's
+/// missing.
+pub(crate) fn normalize_course_id(id: &str) -> Ustr {
+    if id.starts_with(COURSE_ID_PREFIX) {
+        Ustr::from(id)
+    } else {
+        Ustr::from(&format!("{COURSE_ID_PREFIX}{id}"))
+    }
+}
+
+/// Returns the directory of the course with the given ID, with or without the
+/// `trane::transcription::` prefix. Each segment of the ID after the prefix becomes a directory
+/// under the courses directory.
+pub(crate) fn course_directory(courses_root: &Path, id: &str) -> PathBuf {
+    let path = id
+        .trim_start_matches(COURSE_ID_PREFIX)
+        .split("::")
+        .collect::<Vec<_>>()
+        .join("/");
+    courses_root.join(path)
+}
+
+/// Returns the path at which the manifest of the course with the given ID is expected to be.
+pub(crate) fn manifest_path(courses_root: &Path, id: &str) -> PathBuf {
+    course_directory(courses_root, id).join(MANIFEST_FILE)
+}
+
+/// Returns the transcription config of the course, or `None` if the course does not use the
+/// transcription generator.
+pub(crate) fn transcription_config(manifest: &CourseManifest) -> Option<&TranscriptionConfig> {
+    match &manifest.generator_config {
+        Some(CourseGenerator::Transcription(config)) => Some(config),
+        _ => None,
+    }
+}
+
+/// Returns the path of the file listing the additional external links of the passages of the
+/// course with the given ID.
+pub(crate) fn additional_links_path(courses_root: &Path, id: &str) -> PathBuf {
+    course_directory(courses_root, id).join(ADDITIONAL_LINKS_FILE)
+}
+
+/// Reads the additional external links of the passages of the course with the given ID. A course
+/// without the file has no additional links.
+pub(crate) fn read_additional_links(courses_root: &Path, id: &str) -> Result<AdditionalLinks> {
+    let path = additional_links_path(courses_root, id);
+    if !path.exists() {
+        return Ok(AdditionalLinks::new());
+    }
+    let contents = fs::read_to_string(&path)
+        .with_context(|| format!("failed to read additional links at {}", path.display()))?;
+    serde_json::from_str(&contents)
+        .with_context(|| format!("failed to parse additional links at {}", path.display()))
+}
+
+/// Returns all the external links of the passage, that is, the external link of the track followed
+/// by its additional links.
+pub(crate) fn passage_links(
+    additional_links: &AdditionalLinks,
+    passages: &TranscriptionPassages,
+) -> Vec<ExternalLink> {
+    let TranscriptionAsset::Track {
+        short_id,
+        external_link,
+        ..
+    } = &passages.asset;
+    external_link
+        .iter()
+        .map(ExternalLink::from)
+        .chain(
+            additional_links
+                .get(short_id)
+                .into_iter()
+                .flatten()
+                .map(|url| ExternalLink::from(url.as_str())),
+        )
+        .collect()
+}
+
+/// Returns the URLs of all the external links of the passage, in the same order as `passage_links`,
+/// so that they can be rewritten in place. `additional_links` are the additional links of the
+/// course containing the passage.
+pub(crate) fn passage_links_mut<'a>(
+    additional_links: &'a mut AdditionalLinks,
+    passages: &'a mut TranscriptionPassages,
+) -> Vec<&'a mut String> {
+    let TranscriptionAsset::Track {
+        short_id,
+        external_link,
+        ..
+    } = &mut passages.asset;
+    external_link
+        .iter_mut()
+        .map(|TranscriptionLink::YouTube(url)| url)
+        .chain(
+            additional_links
+                .get_mut(short_id.as_str())
+                .into_iter()
+                .flatten(),
+        )
+        .collect()
+}
+
+/// Returns the manifests and transcription configs of the transcription courses among the given
+/// courses, looking up the manifest of each with `get_manifest`. Courses whose manifest is missing
+/// are skipped with a warning, so that the courses that do load can still be processed.
+fn filter_transcription_courses(
+    course_ids: Vec<Ustr>,
+    get_manifest: impl Fn(Ustr) -> Option<CourseManifest>,
+) -> Vec<(CourseManifest, TranscriptionConfig)> {
+    course_ids
+        .into_iter()
+        .filter_map(|course_id| {
+            let Some(manifest) = get_manifest(course_id) else {
+                eprintln!("Warning: skipped course {course_id} because its manifest is missing");
+                return None;
+            };
+            let config = transcription_config(&manifest)?.clone();
+            Some((manifest, config))
+        })
+        .collect()
+}
+
+/// Returns the manifests and transcription configs of all the transcription courses in the library,
+/// sorted by course ID.
+pub(crate) fn transcription_courses(trane: &Trane) -> Vec<(CourseManifest, TranscriptionConfig)> {
+    filter_transcription_courses(trane.get_course_ids(), |course_id| {
+        trane.get_course_manifest(course_id)
+    })
+}
+
+/// Returns the patterns in the `--only` filter as fully-qualified course IDs.
+pub(crate) fn course_patterns(only: &[String]) -> Vec<Ustr> {
+    only.iter().map(|id| normalize_course_id(id)).collect()
+}
+
+/// Returns whether the text matches the glob pattern, in which `*` matches any characters within a
+/// single segment of the ID and `**` matches any characters, including the `::` separators.
+fn glob_matches(pattern: &[u8], text: &[u8]) -> bool {
+    match pattern {
+        [] => text.is_empty(),
+        [b'*', b'*', rest @ ..] => (0..=text.len()).any(|start| glob_matches(rest, &text[start..])),
+        [b'*', rest @ ..] => {
+            let mut start = 0;
+            loop {
+                if glob_matches(rest, &text[start..]) {
+                    return true;
+                }
+                if start == text.len() || text[start] == b':' {
+                    return false;
+                }
+                start += 1;
+            }
+        }
+        [c, rest @ ..] => text.first() == Some(c) && glob_matches(rest, &text[1..]),
+    }
+}
+
+/// Returns whether the course ID matches the pattern from the `--only` filter. Patterns without a
+/// wildcard must match the ID exactly.
+pub(crate) fn matches_course_pattern(pattern: &str, course_id: &str) -> bool {
+    if pattern.contains('*') {
+        glob_matches(pattern.as_bytes(), course_id.as_bytes())
+    } else {
+        pattern == course_id
+    }
+}
+
+/// Returns whether the course is selected by the `--only` filter, which selects all the courses if
+/// it's empty.
+pub(crate) fn is_selected(only: &[Ustr], course_id: Ustr) -> bool {
+    only.is_empty()
+        || only
+            .iter()
+            .any(|pattern| matches_course_pattern(pattern, &course_id))
+}
+
+/// Returns whether the course matches any of the patterns in the `--exclude` filter.
+pub(crate) fn is_excluded(exclude: &[Ustr], course_id: Ustr) -> bool {
+    exclude
+        .iter()
+        .any(|pattern| matches_course_pattern(pattern, &course_id))
+}
+
+/// Prints a warning for each of the course IDs or patterns in the `--only` filter that does not
+/// match any of the given courses.
+pub(crate) fn warn_unknown_courses(only: &[Ustr], course_ids: &[Ustr]) {
+    for pattern in only {
+        if course_ids
+            .iter()
+            .any(|course_id| matches_course_pattern(pattern, course_id))
+        {
+            continue;
+        }
+        if pattern.contains('*') {
+            eprintln!("Warning: no course matches {pattern}");
+        } else {
+            eprintln!("Warning: course {pattern} does not exist");
+        }
+    }
+}
+
+/// Returns the courses directory under the given repository root, failing if it does not exist.
+pub(crate) fn courses_root(root: &Path) -> Result<PathBuf> {
+    let courses_root = root.join(COURSES_DIRECTORY);
+    if !courses_root.exists() {
+        bail!(
+            "courses directory does not exist at {}",
+            courses_root.display()
+        );
+    }
+    Ok(courses_root)
+}
+
+/// Opens the trane-transcription library located at the given repository root in trane.
+pub(crate) fn open_library(root: &Path) -> Result<Trane> {
+    Trane::new_local(root, root)
+        .with_context(|| format!("failed to open library at {}", root.display()))
+}
+
+/// Returns the paths to all the course manifests under the courses directory, sorted
+/// alphabetically.
+pub(crate) fn find_manifests(courses_root: &Path) -> Result<Vec<PathBuf>> {
+    let mut paths = vec![];
+    for entry in WalkDir::new(courses_root) {
+        let entry = entry.with_context(|| {
+            format!(
+                "failed to read courses directory at {}",
+                courses_root.display()
+            )
+        })?;
+        if entry.file_type().is_file() && entry.file_name() == MANIFEST_FILE {
+            paths.push(entry.into_path());
+        }
+    }
+    paths.sort();
+    Ok(paths)
+}
+
+/// Returns the paths to the manifests of the courses nested under the directory of a course, that
+/// is, every manifest under `directory` except the one of the course itself, sorted alphabetically.
+pub(crate) fn nested_manifests(directory: &Path) -> Result<Vec<PathBuf>> {
+    let own_manifest = directory.join(MANIFEST_FILE);
+    Ok(find_manifests(directory)?
+        .into_iter()
+        .filter(|path| *path != own_manifest)
+        .collect())
+}
+
+/// Reads the course manifest at the given path.
+pub(crate) fn read_manifest(path: &Path) -> Result<CourseManifest> {
+    let contents = fs::read_to_string(path)
+        .with_context(|| format!("failed to read course manifest at {}", path.display()))?;
+    serde_json::from_str(&contents)
+        .with_context(|| format!("failed to parse course manifest at {}", path.display()))
+}
+
+/// Reads the manifest of the course with the given ID, with or without the `trane::transcription::`
+/// prefix, and returns it along with its path. Fails if the course does not exist.
+pub(crate) fn read_course(courses_root: &Path, id: &str) -> Result<(PathBuf, CourseManifest)> {
+    let path = manifest_path(courses_root, id);
+    if !path.exists() {
+        bail!("course does not exist at {}", path.display());
+    }
+    let manifest = read_manifest(&path)?;
+    Ok((path, manifest))
+}
+
+/// Reads all the course manifests under the courses directory along with their paths, sorted by
+/// path.
+pub(crate) fn read_all_manifests(courses_root: &Path) -> Result<Vec<(PathBuf, CourseManifest)>> {
+    find_manifests(courses_root)?
+        .into_iter()
+        .map(|path| {
+            let manifest = read_manifest(&path)?;
+            Ok((path, manifest))
+        })
+        .collect()
+}
+
+/// Serializes the value as JSON using the formatting shared by all the manifests in this
+/// repository.
+pub(crate) fn to_pretty_json(value: &impl Serialize) -> Result<Vec<u8>> {
+    let formatter = serde_json::ser::PrettyFormatter::with_indent(b"    ");
+    let mut buf = Vec::new();
+    let mut ser = serde_json::Serializer::with_formatter(&mut buf, formatter);
+    value
+        .serialize(&mut ser)
+        .with_context(|| "failed to serialize value to JSON")?;
+    Ok(buf)
+}
+
+/// Serializes the course manifest using the formatting shared by all the manifests in this
+/// repository.
+pub(crate) fn serialize_manifest(manifest: &CourseManifest) -> Result<Vec<u8>> {
+    to_pretty_json(manifest)
+        .with_context(|| format!("failed to serialize manifest of {}", manifest.id))
+}
+
+/// Returns the path of a file next to the given one, with the extension appended to its name.
+fn sibling_path(path: &Path, extension: &str) -> Result<PathBuf> {
+    let file_name = path
+        .file_name()
+        .with_context(|| format!("invalid path {}", path.display()))?;
+    let mut sibling_name = file_name.to_os_string();
+    sibling_name.push(extension);
+    Ok(path.with_file_name(sibling_name))
+}
+
+/// Writes the contents to the given path atomically, by writing them to a temporary file in the
+/// same directory and then renaming it, so the file is never left partially written. If `backup`
+/// is true and the file already exists, its previous contents are copied to a `.bak` file next to
+/// it.
+pub(crate) fn write_atomically(path: &Path, contents: &[u8], backup: bool) -> Result<()> {
+    let temp_path = sibling_path(path, ".tmp")?;
+    if let Err(err) = fs::write(&temp_path, contents) {
+        // Do not leave a partially written temporary file behind.
+        if temp_path.is_file() {
+            let _ = fs::remove_file(&temp_path);
+        }
+        return Err(err).with_context(|| format!("failed to write to {}", temp_path.display()));
+    }
+    if backup && path.exists() {
+        let backup_path = sibling_path(path, ".bak")?;
+        fs::copy(path, &backup_path).with_context(|| {
+            format!(
+                "failed to back up {} to {}",
+                path.display(),
+                backup_path.display()
+            )
+        })?;
+    }
+    fs::rename(&temp_path, path).with_context(|| {
+        format!(
+            "failed to move {} to {}",
+            temp_path.display(),
+            path.display()
+        )
+    })
+}
+
+/// Writes the course manifest to the given path atomically. If `backup` is true, the previous
+/// manifest, if any, is kept in a `.bak` file next to it.
+pub(crate) fn write_manifest(path: &Path, manifest: &CourseManifest, backup: bool) -> Result<()> {
+    let buf = serialize_manifest(manifest)?;
+    write_atomically(path, &buf, backup)
+        .with_context(|| format!("failed to write course manifest to {}", path.display()))
+}
+
+#[cfg(test)]
+mod test {
+    use anyhow::Result;
+    use std::{collections::HashMap, fs};
+    use trane::data::{
+        course_generator::transcription::{
+            TranscriptionAsset, TranscriptionLink, TranscriptionPassages,
+        },
+        CourseGenerator, CourseManifestBuilder,
+    };
+    use ustr::Ustr;
+
+    use super::{
+        additional_links_path, filter_transcription_courses, matches_course_pattern,
+        nested_manifests, passage_links, passage_links_mut, read_additional_links,
+        write_atomically, AdditionalLinks, ADDITIONAL_LINKS_FILE, MANIFEST_FILE,
+    };
+    use crate::{links::ExternalLink, templates::CourseTemplate};
+
+    /// Verifies that course IDs are matched against exact IDs and glob patterns.
+    #[test]
+    fn course_patterns() {
+        let id = "trane::transcription::jazz::miles_davis::so_what";
+        assert!(matches_course_pattern(id, id));
+        assert!(!matches_course_pattern("trane::transcription::jazz", id));
+        assert!(matches_course_pattern(
+            "trane::transcription::jazz::*::so_what",
+            id
+        ));
+        assert!(matches_course_pattern(
+            "trane::transcription::jazz::miles_*::*",
+            id
+        ));
+        assert!(!matches_course_pattern("trane::transcription::jazz::*", id));
+        assert!(matches_course_pattern("trane::transcription::jazz::**", id));
+        assert!(matches_course_pattern(
+            "trane::transcription::**::so_what",
+            id
+        ));
+        assert!(!matches_course_pattern(
+            "trane::transcription::folk::**",
+            id
+        ));
+    }
+
+    /// Verifies that courses without a manifest and courses not generated by this tool are skipped
+    /// instead of aborting the collection of the transcription courses.
+    #[test]
+    fn skip_missing_manifests() {
+        let manifest = |id: &str, transcription: bool| {
+            CourseManifestBuilder::default()
+                .id(Ustr::from(id))
+                .name(id.to_string())
+                .generator_config(
+                    transcription
+                        .then(|| CourseGenerator::Transcription(CourseTemplate::Blank.config())),
+                )
+                .build()
+                .unwrap()
+        };
+        let manifests = HashMap::from([
+            (Ustr::from("a"), manifest("a", true)),
+            (Ustr::from("c"), manifest("c", false)),
+            (Ustr::from("d"), manifest("d", true)),
+        ]);
+        let course_ids = ["a", "b", "c", "d"].into_iter().map(Ustr::from).collect();
+        let courses = filter_transcription_courses(course_ids, |course_id| {
+            manifests.get(&course_id).cloned()
+        });
+        assert_eq!(
+            courses
+                .iter()
+                .map(|(manifest, _)| manifest.id)
+                .collect::<Vec<_>>(),
+            vec![Ustr::from("a"), Ustr::from("d")]
+        );
+    }
+
+    /// Verifies that the additional links of a passage follow the external link of the track, keep
+    /// the platform of each URL, and can be rewritten in place.
+    #[test]
+    fn additional_passage_links() {
+        let mut additional_links = AdditionalLinks::from([(
+            "track".to_string(),
+            vec!["https://open.spotify.com/track/1".to_string()],
+        )]);
+        let mut passages = TranscriptionPassages {
+            asset: TranscriptionAsset::Track {
+                short_id: "track".to_string(),
+                track_name: "Track".to_string(),
+                artist_name: None,
+                album_name: None,
+                duration: None,
+                external_link: Some(TranscriptionLink::YouTube(
+                    "https://youtu.be/h8Pv04Tj5GE".to_string(),
+                )),
+            },
+            intervals: HashMap::new(),
+        };
+        assert_eq!(
+            passage_links(&additional_links, &passages),
+            vec![
+                ExternalLink::YouTube("https://youtu.be/h8Pv04Tj5GE".to_string()),
+                ExternalLink::Spotify("https://open.spotify.com/track/1".to_string()),
+            ]
+        );
+
+        for url in passage_links_mut(&mut additional_links, &mut passages) {
+            url.push_str("?si=x");
+        }
+        assert_eq!(
+            passage_links(&additional_links, &passages)
+                .iter()
+                .map(ExternalLink::url)
+                .collect::<Vec<_>>(),
+            vec![
+                "https://youtu.be/h8Pv04Tj5GE?si=x",
+                "https://open.spotify.com/track/1?si=x"
+            ]
+        );
+    }
+
+    /// Verifies that the additional links are read from the file in the directory of the course,
+    /// and that a course without the file has none.
+    #[test]
+    fn read_additional_links_file() -> Result<()> {
+        let courses_root = tempfile::tempdir()?;
+        assert!(read_additional_links(courses_root.path(), "trane::transcription::a")?.is_empty());
+
+        let path = additional_links_path(courses_root.path(), "trane::transcription::a");
+        assert_eq!(
+            path,
+            courses_root.path().join("a").join(ADDITIONAL_LINKS_FILE)
+        );
+        fs::create_dir_all(path.parent().unwrap())?;
+        fs::write(&path, r#"{"track": ["https://vimeo.com/1"]}"#)?;
+        assert_eq!(
+            read_additional_links(courses_root.path(), "trane::transcription::a")?,
+            AdditionalLinks::from([("track".to_string(), vec!["https://vimeo.com/1".to_string()])])
+        );
+        Ok(())
+    }
+
+    /// Verifies that only the manifests of the courses under the directory of another course are
+    /// reported as nested.
+    #[test]
+    fn find_nested_manifests() -> Result<()> {
+        let directory = tempfile::tempdir()?;
+        let course = directory.path().join("course");
+        fs::create_dir_all(course.join("nested"))?;
+        fs::create_dir_all(directory.path().join("sibling"))?;
+        fs::write(course.join(MANIFEST_FILE), "{}")?;
+        fs::write(directory.path().join("sibling").join(MANIFEST_FILE), "{}")?;
+        assert!(nested_manifests(&course)?.is_empty());
+
+        fs::write(course.join("nested").join(MANIFEST_FILE), "{}")?;
+        assert_eq!(
+            nested_manifests(&course)?,
+            vec![course.join("nested").join(MANIFEST_FILE)]
+        );
+        Ok(())
+    }
+
+    /// Verifies that the previous contents are backed up when requested.
+    #[test]
+    fn write_with_backup() -> Result<()> {
+        let directory = tempfile::tempdir()?;
+        let path = directory.path().join("course_manifest.json");
+        write_atomically(&path, b"first", true)?;
+        assert!(!directory.path().join("course_manifest.json.bak").exists());
+
+        write_atomically(&path, b"second", false)?;
+        assert!(!directory.path().join("course_manifest.json.bak").exists());
+
+        write_atomically(&path, b"third", true)?;
+        assert_eq!(fs::read_to_string(&path)?, "third");
+        assert_eq!(
+            fs::read_to_string(directory.path().join("course_manifest.json.bak"))?,
+            "second"
+        );
+        assert!(!directory.path().join("course_manifest.json.tmp").exists());
+        Ok(())
+    }
+
+    /// Verifies that a failed write leaves the original file intact.
+    #[test]
+    fn failed_write_keeps_original() -> Result<()> {
+        let directory = tempfile::tempdir()?;
+        let path = directory.path().join("course_manifest.json");
+        fs::write(&path, "original")?;
+
+        // A directory in place of the temporary file makes the write fail.
+        fs::create_dir(directory.path().join("course_manifest.json.tmp"))?;
+        assert!(write_atomically(&path, b"updated", true).is_err());
+        assert_eq!(fs::read_to_string(&path)?, "original");
+        assert!(!directory.path().join("course_manifest.json.bak").exists());
+        Ok(())
+    }
+}
@@ -0,0 +1,1743 @@
+//! Contains the verification of the external links in the inlined passages of the transcription
+//! courses.
+
+use std::{
+    io::{self, IsTerminal, Write},
+    path::Path,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Mutex,
+    },
+    thread,
+    time::Duration,
+};
+
+use anyhow::{bail, Result};
+use clap::ValueEnum;
+use log::debug;
+use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
+use serde::{Deserialize, Serialize};
+use trane::{
+    course_library::CourseLibrary, data::course_generator::transcription::TranscriptionLink, Trane,
+};
+use url::Url;
+use ustr::Ustr;
+
+use crate::{
+    cache::LinkCache,
+    exit::{self, Failure, FailureKind},
+    ignore, library,
+    output::{self, status},
+    reports::ReportFormat,
+};
+
+/// The time to wait before the first retry of a failed verification. The time is doubled after
+/// each retry.
+const RETRY_BACKOFF: Duration = Duration::from_millis(500);
+
+/// The `User-Agent` header sent with the requests to verify the links unless overridden. Some
+/// servers reject requests that do not identify the client.
+pub(crate) const DEFAULT_USER_AGENT: &str =
+    concat!(env!("CARGO_PKG_NAME"), "/", env!("CARGO_PKG_VERSION"));
+
+/// The status of a link reported by a single attempt to verify it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum LinkStatus {
+    /// The link refers to a valid recording.
+    Valid,
+
+    /// The link does not refer to a valid recording, for example because it was removed or is
+    /// malformed.
+    NotFound,
+
+    /// The link refers to a recording that cannot be played by everyone, for the given reason.
+    Restricted(String),
+
+    /// The link could not be verified, so its validity is unknown. These errors are worth retrying.
+    NetworkError {
+        /// The description of the error.
+        message: String,
+
+        /// Whether the error was caused by the request timing out.
+        timed_out: bool,
+    },
+}
+
+/// The subset of the fields of an oembed response used to verify a link.
+#[derive(Debug, Deserialize)]
+struct OembedResponse {
+    /// The title of the video.
+    #[serde(default)]
+    title: String,
+}
+
+/// Logs the URL of the request along with the status of the response, or the error if no response
+/// was received.
+fn log_request(url: &str, res: &Result<ureq::Response, ureq::Error>) {
+    match res {
+        Ok(res) => debug!("Request to {url} returned status {}", res.status()),
+        Err(ureq::Error::Status(status, _)) => {
+            debug!("Request to {url} returned status {status}")
+        }
+        Err(e) => debug!("Request to {url} failed: {e}"),
+    }
+}
+
+/// Returns whether the transport error was caused by the request timing out.
+fn is_timeout(transport: &ureq::Transport) -> bool {
+    std::error::Error::source(transport)
+        .and_then(|source| source.downcast_ref::<io::Error>())
+        .is_some_and(|e| {
+            matches!(
+                e.kind(),
+                io::ErrorKind::TimedOut | io::ErrorKind::WouldBlock
+            )
+        })
+}
+
+/// Returns the status of a link given the error returned by the request to verify it. Server
+/// errors, rate limiting, and request timeouts say nothing about the link, so they are treated like
+/// network errors and retried.
+fn error_status(e: ureq::Error) -> LinkStatus {
+    match e {
+        ureq::Error::Status(status @ (408 | 429), _) => LinkStatus::NetworkError {
+            message: format!("the server returned status {status}"),
+            timed_out: status == 408,
+        },
+        ureq::Error::Status(status, _) if status >= 500 => LinkStatus::NetworkError {
+            message: format!("the server returned status {status}"),
+            timed_out: false,
+        },
+        ureq::Error::Status(..) => LinkStatus::NotFound,
+        ureq::Error::Transport(transport) => LinkStatus::NetworkError {
+            timed_out: is_timeout(&transport),
+            message: transport.to_string(),
+        },
+    }
+}
+
+/// Sends the request to verify a link and logs the result. If the request fails, returns the
+/// resulting status of the link as the error.
+fn send(request: ureq::Request) -> Result<ureq::Response, LinkStatus> {
+    let url = request.url().to_string();
+    let res = request.call();
+    log_request(&url, &res);
+    res.map_err(error_status)
+}
+
+/// Returns whether the YouTube video page indicates that the video is age-restricted. Errors while
+/// retrieving the page are ignored since the oembed request already verified the video exists.
+/// This downloads the whole watch page, so it doubles the number of requests per link and is much
+/// heavier than the oembed request, and it relies on markers in the page that YouTube may change.
+fn is_age_restricted(agent: &ureq::Agent, link: &str) -> bool {
+    let Ok(res) = send(agent.get(link)) else {
+        return false;
+    };
+    let Ok(page) = res.into_string() else {
+        return false;
+    };
+    page.contains("\"isFamilySafe\":false")
+        || page.contains("\"status\":\"AGE_VERIFICATION_REQUIRED\"")
+        || page.contains("\"status\":\"LOGIN_REQUIRED\"")
+}
+
+/// Returns the URL of the oembed endpoint describing the link. The link is percent-encoded so that
+/// its own query string is not mixed with the query of the endpoint. `suffix` holds any other
+/// parameters of the query, starting with `&`.
+fn oembed_query(endpoint: &str, link: &str, suffix: &str) -> String {
+    format!(
+        "{endpoint}?url={}{suffix}",
+        utf8_percent_encode(link, NON_ALPHANUMERIC)
+    )
+}
+
+/// Verifies that a YouTube link refers to a valid video that can be played by everyone. Private and
+/// members-only videos are reported as restricted, as are age-restricted videos if
+/// `check_age_restriction` is true.
+fn verify_youtube_link(agent: &ureq::Agent, link: &str, check_age_restriction: bool) -> LinkStatus {
+    // Query the canonical form of the link so that timestamps and other parameters do not interfere
+    // with the oembed query.
+    let Some(video_id) = parse_youtube_video_id(link) else {
+        return LinkStatus::NotFound;
+    };
+    let video_url = format!("https://www.youtube.com/watch?v={video_id}");
+
+    // Use the oembed format to retrieve a small amount of data. Private and members-only videos
+    // are reported as unauthorized.
+    let url = oembed_query("https://www.youtube.com/oembed", &video_url, "&format=json");
+    let res = agent.get(&url).call();
+    log_request(&url, &res);
+    let res = match res {
+        Ok(res) => res,
+        Err(ureq::Error::Status(401 | 403, _)) => {
+            return LinkStatus::Restricted("the video is private or unavailable".to_string())
+        }
+        Err(e) => return error_status(e),
+    };
+    if res.status() != 200 {
+        return LinkStatus::NotFound;
+    }
+
+    // Videos that cannot be played are sometimes returned without a title.
+    let body = match res.into_string() {
+        Ok(body) => body,
+        Err(e) => {
+            return LinkStatus::NetworkError {
+                message: e.to_string(),
+                timed_out: false,
+            }
+        }
+    };
+    let Ok(response) = serde_json::from_str::<OembedResponse>(&body) else {
+        return LinkStatus::NotFound;
+    };
+    if response.title.is_empty() {
+        return LinkStatus::Restricted("the video has no title".to_string());
+    }
+    if check_age_restriction && is_age_restricted(agent, &video_url) {
+        return LinkStatus::Restricted("the video is age-restricted".to_string());
+    }
+    LinkStatus::Valid
+}
+
+/// Verifies that the page of a YouTube video responds to a HEAD request. This is faster than the
+/// oembed request, but cannot detect restricted videos.
+fn verify_youtube_page(agent: &ureq::Agent, link: &str) -> LinkStatus {
+    let Some(video_id) = parse_youtube_video_id(link) else {
+        return LinkStatus::NotFound;
+    };
+    let video_url = format!("https://www.youtube.com/watch?v={video_id}");
+    match send(agent.head(&video_url)) {
+        Ok(res) if (200..400).contains(&res.status()) => LinkStatus::Valid,
+        Ok(_) => LinkStatus::NotFound,
+        Err(status) => status,
+    }
+}
+
+/// Returns the status of a link verified with an oembed request, which only succeeds if the link
+/// refers to a valid recording.
+fn oembed_status(res: Result<ureq::Response, LinkStatus>) -> LinkStatus {
+    match res {
+        Ok(res) if res.status() == 200 => LinkStatus::Valid,
+        Ok(_) => LinkStatus::NotFound,
+        Err(status) => status,
+    }
+}
+
+/// Verifies that a Vimeo link refers to a valid video.
+fn verify_vimeo_link(agent: &ureq::Agent, link: &str) -> LinkStatus {
+    // Use the oembed format to retrieve a small amount of data. Private or deleted videos return an
+    // error status.
+    let url = oembed_query("https://vimeo.com/api/oembed.json", link, "");
+    oembed_status(send(agent.get(&url)))
+}
+
+/// Verifies that a SoundCloud link refers to a valid track.
+fn verify_soundcloud_link(agent: &ureq::Agent, link: &str) -> LinkStatus {
+    // Use the oembed format to retrieve a small amount of data. Deleted tracks return an error
+    // status.
+    let url = oembed_query("https://soundcloud.com/oembed", link, "&format=json");
+    oembed_status(send(agent.get(&url)))
+}
+
+/// Verifies that a Spotify link refers to a valid track, album, or episode.
+fn verify_spotify_link(agent: &ureq::Agent, link: &str) -> LinkStatus {
+    // Only links to tracks, albums, and episodes refer to a recording that can be transcribed.
+    let is_recording = ["/track/", "/album/", "/episode/"]
+        .iter()
+        .any(|kind| link.contains(kind));
+    if !is_recording {
+        return LinkStatus::NotFound;
+    }
+
+    // Use the oembed format to retrieve a small amount of data. Removed or unavailable recordings
+    // return an error status.
+    let url = oembed_query("https://open.spotify.com/oembed", link, "");
+    oembed_status(send(agent.get(&url)))
+}
+
+/// Verifies that a link not hosted on any of the known platforms can be retrieved. Only the
+/// headers are requested, and any successful or redirect status is considered valid.
+fn verify_generic_link(agent: &ureq::Agent, link: &str) -> LinkStatus {
+    match send(agent.head(link)) {
+        Ok(res) if (200..400).contains(&res.status()) => LinkStatus::Valid,
+        Ok(_) => LinkStatus::NotFound,
+        Err(status) => status,
+    }
+}
+
+/// Returns whether the string has the form of a YouTube video ID, that is, eleven alphanumeric
+/// characters, dashes, or underscores.
+fn is_youtube_video_id(id: &str) -> bool {
+    id.len() == 11
+        && id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// Returns the ID of the YouTube video referred to by the link, or `None` if the link is not a
+/// valid YouTube video link. Watch, `youtu.be`, shorts, and embed links are supported, including
+/// embed links on the `youtube-nocookie.com` domain, and other query parameters such as `t` and
+/// `list` are ignored.
+pub(crate) fn parse_youtube_video_id(link: &str) -> Option<String> {
+    let link = link.trim();
+    let rest = link
+        .strip_prefix("https://")
+        .or_else(|| link.strip_prefix("http://"))
+        .unwrap_or(link);
+    let (host, path) = rest.split_once('/')?;
+    let host = host
+        .strip_prefix("www.")
+        .or_else(|| host.strip_prefix("m."))
+        .unwrap_or(host);
+    let (path, query) = path.split_once('?').unwrap_or((path, ""));
+    let path = path.split('#').next().unwrap_or_default();
+    let query = query.split('#').next().unwrap_or_default();
+
+    let id = match host {
+        "youtu.be" => path.split('/').next()?,
+        "youtube.com" => {
+            if path == "watch" {
+                query
+                    .split('&')
+                    .find_map(|parameter| parameter.strip_prefix("v="))?
+            } else {
+                let id = path
+                    .strip_prefix("shorts/")
+                    .or_else(|| path.strip_prefix("embed/"))?;
+                id.split('/').next()?
+            }
+        }
+        "youtube-nocookie.com" => path.strip_prefix("embed/")?.split('/').next()?,
+        _ => return None,
+    };
+    is_youtube_video_id(id).then(|| id.to_string())
+}
+
+/// Returns a link to the YouTube video that starts playing at `start` and stops at `end`, both in
+/// seconds. The start is encoded in the `t` parameter and the end in the `end` parameter, and both
+/// are ignored when the link is verified. Fails if the link is not a YouTube video link or the
+/// start is not before the end.
+pub(crate) fn youtube_link_with_range(
+    link: &str,
+    start: Option<u32>,
+    end: Option<u32>,
+) -> Result<String> {
+    let Some(video_id) = parse_youtube_video_id(link) else {
+        bail!("{link} is not a YouTube video link");
+    };
+    if let (Some(start), Some(end)) = (start, end) {
+        if start >= end {
+            bail!("the start of the passage ({start}s) must be before its end ({end}s)");
+        }
+    }
+
+    let mut url = format!("https://www.youtube.com/watch?v={video_id}");
+    if let Some(start) = start {
+        url.push_str(&format!("&t={start}"));
+    }
+    if let Some(end) = end {
+        url.push_str(&format!("&end={end}"));
+    }
+    Ok(url)
+}
+
+/// Returns the canonical form of the YouTube link, `https://www.youtube.com/watch?v=<id>`, keeping
+/// the time at which the video starts and stops playing, if any. The `start` parameter of embed
+/// links is converted to `t`. Returns `None` if the link is not a YouTube video link.
+pub(crate) fn normalize_youtube_link(link: &str) -> Option<String> {
+    let video_id = parse_youtube_video_id(link)?;
+    let query = link
+        .trim()
+        .split_once('?')
+        .map(|(_, query)| query.split('#').next().unwrap_or_default())
+        .unwrap_or_default();
+    let parameter = |name: &str| {
+        query.split('&').find_map(|parameter| {
+            parameter
+                .strip_prefix(name)
+                .and_then(|value| value.strip_prefix('='))
+                .filter(|value| !value.is_empty())
+        })
+    };
+
+    let mut url = format!("https://www.youtube.com/watch?v={video_id}");
+    if let Some(start) = parameter("t").or_else(|| parameter("start")) {
+        url.push_str(&format!("&t={start}"));
+    }
+    if let Some(end) = parameter("end") {
+        url.push_str(&format!("&end={end}"));
+    }
+    Some(url)
+}
+
+/// Returns the canonical form of the link used to find links to the same video. YouTube links are
+/// reduced to the ID of the video, and other links are compared as they are.
+pub(crate) fn canonical_link(link: &str) -> String {
+    match parse_youtube_video_id(link) {
+        Some(id) => format!("youtube:{id}"),
+        None => link.trim().to_string(),
+    }
+}
+
+/// A platform hosting external links.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
+pub(crate) enum Platform {
+    /// YouTube.
+    #[clap(name = "youtube")]
+    YouTube,
+
+    /// Vimeo.
+    Vimeo,
+
+    /// SoundCloud.
+    #[clap(name = "soundcloud")]
+    SoundCloud,
+
+    /// Spotify.
+    Spotify,
+
+    /// Any page not hosted on any of the other platforms.
+    Web,
+}
+
+impl Platform {
+    /// Returns the name of the platform.
+    pub fn name(self) -> &'static str {
+        match self {
+            Platform::YouTube => "YouTube",
+            Platform::Vimeo => "Vimeo",
+            Platform::SoundCloud => "SoundCloud",
+            Platform::Spotify => "Spotify",
+            Platform::Web => "web",
+        }
+    }
+}
+
+/// An external link to verify. trane's `TranscriptionLink` only contains a variant for YouTube, so
+/// the real platform of the link is inferred from its URL until trane supports more platforms.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum ExternalLink {
+    /// A link to a YouTube video.
+    YouTube(String),
+
+    /// A link to a Vimeo video.
+    Vimeo(String),
+
+    /// A link to a SoundCloud track.
+    SoundCloud(String),
+
+    /// A link to a Spotify track, album, or episode.
+    Spotify(String),
+
+    /// A link to a page not hosted on any of the known platforms.
+    Generic(String),
+}
+
+impl From<&TranscriptionLink> for ExternalLink {
+    fn from(link: &TranscriptionLink) -> Self {
+        ExternalLink::from(link.url())
+    }
+}
+
+/// Returns whether the host is the given domain or one of its subdomains.
+fn is_on_domain(host: &str, domain: &str) -> bool {
+    host.strip_suffix(domain)
+        .is_some_and(|subdomain| subdomain.is_empty() || subdomain.ends_with('.'))
+}
+
+impl From<&str> for ExternalLink {
+    fn from(url: &str) -> Self {
+        // Only the host is matched, so that links that mention a platform in their path or query
+        // are not mistaken for links to it.
+        let host = Url::parse(url.trim())
+            .ok()
+            .and_then(|parsed| parsed.host_str().map(str::to_string))
+            .unwrap_or_default();
+        let is_on = |domains: &[&str]| domains.iter().any(|domain| is_on_domain(&host, domain));
+        if is_on(&["vimeo.com"]) {
+            ExternalLink::Vimeo(url.to_string())
+        } else if is_on(&["soundcloud.com"]) {
+            ExternalLink::SoundCloud(url.to_string())
+        } else if is_on(&["open.spotify.com"]) {
+            ExternalLink::Spotify(url.to_string())
+        } else if is_on(&["youtube.com", "youtube-nocookie.com", "youtu.be"]) {
+            ExternalLink::YouTube(url.to_string())
+        } else {
+            ExternalLink::Generic(url.to_string())
+        }
+    }
+}
+
+impl ExternalLink {
+    /// Returns the platform hosting the link.
+    pub fn platform(&self) -> Platform {
+        match self {
+            ExternalLink::YouTube(_) => Platform::YouTube,
+            ExternalLink::Vimeo(_) => Platform::Vimeo,
+            ExternalLink::SoundCloud(_) => Platform::SoundCloud,
+            ExternalLink::Spotify(_) => Platform::Spotify,
+            ExternalLink::Generic(_) => Platform::Web,
+        }
+    }
+
+    /// Returns the name of the platform hosting the link.
+    pub fn platform_name(&self) -> &'static str {
+        self.platform().name()
+    }
+
+    /// Returns the URL of the link.
+    pub fn url(&self) -> &str {
+        match self {
+            ExternalLink::YouTube(link)
+            | ExternalLink::Vimeo(link)
+            | ExternalLink::SoundCloud(link)
+            | ExternalLink::Spotify(link)
+            | ExternalLink::Generic(link) => link,
+        }
+    }
+
+    /// Returns the URL of the oembed endpoint describing the link, or `None` if the platform of the
+    /// link has no such endpoint or the link is malformed.
+    fn oembed_url(&self) -> Option<String> {
+        match self {
+            ExternalLink::YouTube(link) => {
+                let video_id = parse_youtube_video_id(link)?;
+                Some(oembed_query(
+                    "https://www.youtube.com/oembed",
+                    &format!("https://www.youtube.com/watch?v={video_id}"),
+                    "&format=json",
+                ))
+            }
+            ExternalLink::Vimeo(link) => {
+                Some(oembed_query("https://vimeo.com/api/oembed.json", link, ""))
+            }
+            ExternalLink::SoundCloud(link) => Some(oembed_query(
+                "https://soundcloud.com/oembed",
+                link,
+                "&format=json",
+            )),
+            ExternalLink::Spotify(link) => {
+                Some(oembed_query("https://open.spotify.com/oembed", link, ""))
+            }
+            ExternalLink::Generic(_) => None,
+        }
+    }
+
+    /// Verifies the link using the verifier for its platform and the given agent. If `head_only` is
+    /// set in the options, YouTube links are verified with a HEAD request to the page of the video.
+    fn verify(&self, agent: &ureq::Agent, options: &VerifyLinksOptions) -> LinkStatus {
+        match self {
+            ExternalLink::YouTube(link) if options.head_only => verify_youtube_page(agent, link),
+            ExternalLink::YouTube(link) => {
+                verify_youtube_link(agent, link, options.check_age_restriction)
+            }
+            ExternalLink::Vimeo(link) => verify_vimeo_link(agent, link),
+            ExternalLink::SoundCloud(link) => verify_soundcloud_link(agent, link),
+            ExternalLink::Spotify(link) => verify_spotify_link(agent, link),
+            ExternalLink::Generic(link) => verify_generic_link(agent, link),
+        }
+    }
+}
+
+/// The outcome of verifying a link after all the attempts.
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum LinkOutcome {
+    /// The link refers to a valid video.
+    Valid,
+
+    /// The link does not refer to a valid video.
+    Invalid,
+
+    /// The link refers to a video that cannot be played by everyone, for the given reason.
+    Restricted(String),
+
+    /// The request to verify the link timed out, so its validity is unknown.
+    TimedOut,
+
+    /// The link could not be verified because of a network error, so its validity is unknown.
+    Unreachable(String),
+}
+
+impl LinkOutcome {
+    /// Returns the status of the link reported in the JSON lines output.
+    fn status(&self) -> &'static str {
+        match self {
+            LinkOutcome::Valid => "valid",
+            LinkOutcome::Invalid => "not_found",
+            LinkOutcome::Restricted(_) => "restricted",
+            LinkOutcome::TimedOut => "timeout",
+            LinkOutcome::Unreachable(_) => "network_error",
+        }
+    }
+
+    /// Returns the reason reported for the link in the JSON output, or `None` if the link is valid.
+    fn reason(&self) -> Option<LinkFailureReason> {
+        match self {
+            LinkOutcome::Valid => None,
+            LinkOutcome::Invalid => Some(LinkFailureReason::NotFound),
+            LinkOutcome::Restricted(_) => Some(LinkFailureReason::Restricted),
+            LinkOutcome::TimedOut => Some(LinkFailureReason::Timeout),
+            LinkOutcome::Unreachable(_) => Some(LinkFailureReason::NetworkError),
+        }
+    }
+}
+
+/// Verifies the link, retrying up to `max_retries` times with exponential backoff if the link
+/// could not be verified. Links that are not found or restricted are not retried.
+fn verify_with_retries(
+    agent: &ureq::Agent,
+    link: &ExternalLink,
+    options: &VerifyLinksOptions,
+) -> LinkOutcome {
+    let mut retries = 0;
+    loop {
+        let status = link.verify(agent, options);
+        if status != LinkStatus::Valid {
+            debug!("Verification of {} failed: {status:?}", link.url());
+        }
+        let outcome = match status {
+            LinkStatus::Valid => return LinkOutcome::Valid,
+            LinkStatus::NotFound => return LinkOutcome::Invalid,
+            LinkStatus::Restricted(reason) => return LinkOutcome::Restricted(reason),
+            LinkStatus::NetworkError {
+                timed_out: true, ..
+            } => LinkOutcome::TimedOut,
+            LinkStatus::NetworkError { message, .. } => LinkOutcome::Unreachable(message),
+        };
+        if retries >= options.max_retries {
+            return outcome;
+        }
+        thread::sleep(RETRY_BACKOFF * 2u32.pow(retries));
+        retries += 1;
+    }
+}
+
+/// Returns the agent used to verify links on all platforms, with the timeout and user agent in the
+/// given options. The agent keeps an idle connection to each host for every worker, so that the
+/// workers sharing it reuse their connections instead of opening a new one for each request.
+fn build_agent(options: &VerifyLinksOptions) -> ureq::Agent {
+    ureq::AgentBuilder::new()
+        .timeout_connect(options.timeout)
+        .timeout_read(options.timeout)
+        .user_agent(&options.user_agent)
+        .max_idle_connections_per_host(options.jobs.max(1))
+        .build()
+}
+
+/// Verifies a single link with the given options and returns an error describing why the link is
+/// not valid, if it isn't.
+pub(crate) fn verify_link(link: &ExternalLink, options: &VerifyLinksOptions) -> Result<()> {
+    let agent = build_agent(options);
+    let platform = link.platform_name();
+    match verify_with_retries(&agent, link, options) {
+        LinkOutcome::Valid => Ok(()),
+        LinkOutcome::Invalid => bail!("{} is not a valid {platform} link", link.url()),
+        LinkOutcome::Restricted(reason) => {
+            bail!("{} is a restricted {platform} link: {reason}", link.url())
+        }
+        LinkOutcome::TimedOut => bail!("timed out verifying {}", link.url()),
+        LinkOutcome::Unreachable(error) => bail!("could not verify {}: {error}", link.url()),
+    }
+}
+
+/// Returns the title of the recording the link refers to, as returned by the oembed endpoint of its
+/// platform, or `None` if the title could not be retrieved.
+fn fetch_title(agent: &ureq::Agent, link: &ExternalLink) -> Option<String> {
+    let res = send(agent.get(&link.oembed_url()?)).ok()?;
+    let response = serde_json::from_str::<OembedResponse>(&res.into_string().ok()?).ok()?;
+    Some(response.title).filter(|title| !title.is_empty())
+}
+
+/// Verifies a single URL with the given options without loading the library and prints its
+/// platform, its status, and the title of the recording, if available. Fails if the link is not
+/// valid.
+pub(crate) fn validate_link(url: &str, options: &VerifyLinksOptions) -> Result<()> {
+    let link = ExternalLink::from(url);
+    let agent = build_agent(options);
+    let outcome = verify_with_retries(&agent, &link, options);
+    println!("Platform: {}", link.platform_name());
+    match &outcome {
+        LinkOutcome::Restricted(reason) | LinkOutcome::Unreachable(reason) => {
+            println!("Status: {} ({reason})", outcome.status())
+        }
+        _ => println!("Status: {}", outcome.status()),
+    }
+    if matches!(outcome, LinkOutcome::Valid | LinkOutcome::Restricted(_)) {
+        if let Some(title) = fetch_title(&agent, &link) {
+            println!("Title: {title}");
+        }
+    }
+
+    let platform = link.platform_name();
+    match outcome {
+        LinkOutcome::Valid => Ok(()),
+        LinkOutcome::Invalid | LinkOutcome::Restricted(_) => bail!(Failure::new(
+            FailureKind::InvalidLinks,
+            format!("{url} is not a valid {platform} link")
+        )),
+        LinkOutcome::TimedOut | LinkOutcome::Unreachable(_) => bail!("could not verify {url}"),
+    }
+}
+
+/// An external link found in one of the inlined passages of a course.
+#[derive(Clone, Debug)]
+pub(crate) struct CourseLink {
+    /// The ID of the course containing the link.
+    pub course_id: Ustr,
+
+    /// The short ID of the asset containing the link.
+    pub short_id: String,
+
+    /// The link to verify.
+    pub link: ExternalLink,
+}
+
+/// Collects all the external links in the transcription courses of the library. A passage with
+/// more than one link results in one entry for each of them.
+pub(crate) fn collect_links(trane: &Trane, courses_root: &Path) -> Result<Vec<CourseLink>> {
+    let mut links = vec![];
+    for (manifest, config) in library::transcription_courses(trane) {
+        let additional_links = library::read_additional_links(courses_root, &manifest.id)?;
+        for passages in &config.inlined_passages {
+            for link in library::passage_links(&additional_links, passages) {
+                links.push(CourseLink {
+                    course_id: manifest.id,
+                    short_id: passages.asset.short_id().to_string(),
+                    link,
+                });
+            }
+        }
+    }
+    Ok(links)
+}
+
+/// The options used to verify the links.
+#[derive(Clone, Debug)]
+pub(crate) struct VerifyLinksOptions {
+    /// The number of links to verify in parallel.
+    pub jobs: usize,
+
+    /// Whether to skip links that were verified successfully within `cache_ttl`.
+    pub use_cache: bool,
+
+    /// The amount of time for which a successful verification is cached.
+    pub cache_ttl: Duration,
+
+    /// The number of times to retry a failed verification.
+    pub max_retries: u32,
+
+    /// Whether to return an error if any invalid links are found.
+    pub fail_on_invalid: bool,
+
+    /// Whether to print the result of each link to stdout as a JSON object as soon as it's known.
+    pub json_lines: bool,
+
+    /// Whether to print the number of links verified so far while the verification runs.
+    pub progress: bool,
+
+    /// Whether to also report the valid links, not only those that failed verification.
+    pub report_valid: bool,
+
+    /// Whether to verify YouTube links with a HEAD request to the page of the video instead of the
+    /// oembed request, which is faster but does not detect restricted videos.
+    pub head_only: bool,
+
+    /// Whether to download the page of each YouTube video to detect age-restricted videos. This
+    /// doubles the number of requests per YouTube link, so it is disabled by default.
+    pub check_age_restriction: bool,
+
+    /// The number of invalid links after which the verification stops. All the links are verified
+    /// if `None`.
+    pub max_failures: Option<usize>,
+
+    /// The IDs of the courses whose links are verified. All the courses are verified if empty.
+    pub only: Vec<Ustr>,
+
+    /// The patterns of the courses whose links are skipped even if they are selected by `only`.
+    pub exclude: Vec<Ustr>,
+
+    /// The patterns of the courses whose links are skipped because they match the ignore file.
+    pub ignored: Vec<Ustr>,
+
+    /// The platforms whose links are verified. The links on all platforms are verified if empty.
+    pub platforms: Vec<Platform>,
+
+    /// The maximum time to wait to connect to the host and for each read of the response.
+    pub timeout: Duration,
+
+    /// The value of the `User-Agent` header sent with every request.
+    pub user_agent: String,
+}
+
+impl Default for VerifyLinksOptions {
+    fn default() -> Self {
+        Self {
+            jobs: thread::available_parallelism().map_or(1, |n| n.get()),
+            use_cache: true,
+            cache_ttl: Duration::from_secs(7 * 24 * 60 * 60),
+            max_retries: 2,
+            fail_on_invalid: false,
+            json_lines: false,
+            timeout: Duration::from_secs(15),
+            user_agent: DEFAULT_USER_AGENT.to_string(),
+            only: vec![],
+            exclude: vec![],
+            ignored: vec![],
+            platforms: vec![],
+            progress: io::stderr().is_terminal(),
+            report_valid: false,
+            head_only: false,
+            check_age_restriction: false,
+            max_failures: None,
+        }
+    }
+}
+
+/// The result of verifying a link, as printed in the JSON lines output.
+#[derive(Debug, Serialize)]
+struct LinkResultJson<'a> {
+    /// The ID of the course containing the link.
+    course_id: Ustr,
+
+    /// The short ID of the asset containing the link.
+    short_id: &'a str,
+
+    /// The URL of the link.
+    link: &'a str,
+
+    /// The status of the link.
+    status: &'static str,
+}
+
+/// Prints the result of verifying the link to stdout as a single line of JSON. The line is written
+/// at once so that lines printed by different threads are not interleaved.
+fn print_json_line(course_link: &CourseLink, outcome: &LinkOutcome) {
+    let result = LinkResultJson {
+        course_id: course_link.course_id,
+        short_id: &course_link.short_id,
+        link: course_link.link.url(),
+        status: outcome.status(),
+    };
+    if let Ok(line) = serde_json::to_string(&result) {
+        let mut stdout = io::stdout().lock();
+        let _ = writeln!(stdout, "{line}");
+        let _ = stdout.flush();
+    }
+}
+
+/// Returns the key under which the result of verifying the link with the given options is cached.
+/// The results of the HEAD requests used in head-only mode and those including the age restriction
+/// check are cached under separate keys, so that a verification does not mistake the results of a
+/// less thorough one for its own, and so that repeated runs checking the age restriction skip the
+/// download of the page of the video.
+fn cache_key(link: &ExternalLink, options: &VerifyLinksOptions) -> String {
+    match link {
+        ExternalLink::YouTube(url) if options.head_only => format!("head-only:{url}"),
+        ExternalLink::YouTube(url) if options.check_age_restriction => {
+            format!("age-restriction:{url}")
+        }
+        _ => link.url().to_string(),
+    }
+}
+
+/// Verifies all the given links using the given number of worker threads and returns those that
+/// are not valid, sorted by course ID, short ID, and URL. The valid links are also returned if
+/// `report_valid` is set in the options. All the definitive results are recorded in the cache,
+/// under the key returned by `cache_key`.
+/// If `max_failures` is set in the options, the workers stop once that many invalid links are
+/// found, so the returned number of links that were checked may be less than the number given.
+fn find_failed_links(
+    links: &[CourseLink],
+    options: &VerifyLinksOptions,
+    cache: &Mutex<LinkCache>,
+) -> (Vec<(CourseLink, LinkOutcome)>, usize) {
+    // All the workers share the same agent, which is thread-safe, so connections to each host are
+    // reused across workers.
+    let agent = build_agent(options);
+
+    // Each worker takes the next unchecked link until all of them have been verified.
+    let next = AtomicUsize::new(0);
+    let checked = AtomicUsize::new(0);
+    let failed = Mutex::new(vec![]);
+    let invalid = AtomicUsize::new(0);
+    let stopped = AtomicBool::new(false);
+    thread::scope(|scope| {
+        for _ in 0..options.jobs.max(1) {
+            scope.spawn(|| loop {
+                // Links already being verified by other workers are still reported.
+                if stopped.load(Ordering::Relaxed) {
+                    break;
+                }
+                let index = next.fetch_add(1, Ordering::Relaxed);
+                let Some(course_link) = links.get(index) else {
+                    break;
+                };
+                let key = cache_key(&course_link.link, options);
+                let is_cached =
+                    options.use_cache && cache.lock().unwrap().is_fresh(&key, options.cache_ttl);
+                if options.use_cache {
+                    debug!("Cache {} for {key}", if is_cached { "hit" } else { "miss" });
+                }
+                let outcome = if is_cached {
+                    LinkOutcome::Valid
+                } else {
+                    verify_with_retries(&agent, &course_link.link, options)
+                };
+                if options.progress {
+                    let checked = checked.fetch_add(1, Ordering::Relaxed) + 1;
+                    let mut stderr = io::stderr().lock();
+                    let _ = write!(stderr, "\rChecked {checked} of {} links", links.len());
+                    let _ = stderr.flush();
+                }
+                if options.json_lines {
+                    print_json_line(course_link, &outcome);
+                }
+                if options.report_valid && outcome == LinkOutcome::Valid {
+                    failed
+                        .lock()
+                        .unwrap()
+                        .push((course_link.clone(), LinkOutcome::Valid));
+                }
+                if is_cached {
+                    continue;
+                }
+
+                match outcome {
+                    LinkOutcome::Valid => cache.lock().unwrap().record(&key, true),
+                    LinkOutcome::Invalid => {
+                        cache.lock().unwrap().record(&key, false);
+                        failed.lock().unwrap().push((course_link.clone(), outcome));
+                        let invalid = invalid.fetch_add(1, Ordering::Relaxed) + 1;
+                        if options.max_failures.is_some_and(|max| invalid >= max) {
+                            stopped.store(true, Ordering::Relaxed);
+                        }
+                    }
+                    LinkOutcome::Restricted(_) => {
+                        cache.lock().unwrap().record(&key, false);
+                        failed.lock().unwrap().push((course_link.clone(), outcome));
+                    }
+                    LinkOutcome::TimedOut | LinkOutcome::Unreachable(_) => {
+                        failed.lock().unwrap().push((course_link.clone(), outcome));
+                    }
+                }
+            });
+        }
+    });
+
+    if options.progress && !links.is_empty() {
+        eprintln!();
+    }
+
+    // Sort the results so the output does not depend on the order in which the threads finished.
+    let mut failed = failed.into_inner().unwrap();
+    failed.sort_by(|(a, _), (b, _)| {
+        a.course_id
+            .cmp(&b.course_id)
+            .then_with(|| a.short_id.cmp(&b.short_id))
+            .then_with(|| a.link.url().cmp(b.link.url()))
+    });
+
+    // The workers stop claiming links once the limit is reached, so the links that were checked are
+    // those claimed. Each worker claims one index past the end before it stops.
+    let checked = next.into_inner().min(links.len());
+    (failed, checked)
+}
+
+/// The number of links that failed verification, by the kind of failure.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) struct LinkSummary {
+    /// The number of links that do not refer to a valid video.
+    pub invalid: usize,
+
+    /// The number of links that refer to a video that cannot be played by everyone.
+    pub restricted: usize,
+
+    /// The number of links that could not be verified because of network errors or timeouts.
+    pub unreachable: usize,
+}
+
+/// The reason a link failed verification, as reported in the JSON output.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum LinkFailureReason {
+    /// The link does not refer to a valid video.
+    NotFound,
+
+    /// The link refers to a video that cannot be played by everyone.
+    Restricted,
+
+    /// The request to verify the link timed out.
+    Timeout,
+
+    /// The link could not be verified because of a network error.
+    NetworkError,
+}
+
+/// A link that failed verification, as reported in the JSON output.
+#[derive(Debug, Serialize)]
+struct LinkFailure<'a> {
+    /// The ID of the course containing the link.
+    course_id: Ustr,
+
+    /// The short ID of the asset containing the link.
+    short_id: &'a str,
+
+    /// The URL of the link.
+    link: &'a str,
+
+    /// The reason the link failed verification.
+    reason: LinkFailureReason,
+}
+
+/// The results of verifying the links, as reported in the JSON output.
+#[derive(Debug, Serialize)]
+struct LinkReportJson<'a> {
+    /// The number of links in the courses.
+    total_links: usize,
+
+    /// The number of courses whose links were verified.
+    total_courses: usize,
+
+    /// The number of links that do not refer to a valid video.
+    invalid_links: usize,
+
+    /// The links that failed verification for any reason.
+    failures: Vec<LinkFailure<'a>>,
+
+    /// The result of every verified link, regardless of its status. Only included if the valid
+    /// links are reported.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    results: Option<Vec<LinkResultJson<'a>>>,
+
+    /// The names of the platforms whose links were not verified because of the platform filter.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    skipped_platforms: Vec<&'static str>,
+
+    /// The IDs of the courses whose links were not verified because of the `--exclude` filter.
+    #[serde(skip_serializing_if = "<[Ustr]>::is_empty")]
+    excluded_courses: &'a [Ustr],
+
+    /// The IDs of the courses whose links were not verified because they match the ignore file.
+    #[serde(skip_serializing_if = "<[Ustr]>::is_empty")]
+    ignored_courses: &'a [Ustr],
+
+    /// Whether the verification stopped early because the maximum number of invalid links was
+    /// reached.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    stopped_early: bool,
+}
+
+/// The results of verifying all the links in the transcription courses.
+pub(crate) struct LinkReport {
+    /// The number of links in the courses.
+    pub total_links: usize,
+
+    /// The number of courses whose links were verified.
+    pub total_courses: usize,
+
+    /// The platforms whose links were not verified because of the platform filter.
+    pub skipped_platforms: Vec<Platform>,
+
+    /// The number of links that were not verified because of the platform filter.
+    pub skipped_links: usize,
+
+    /// The IDs of the courses whose links were not verified because of the `--exclude` filter.
+    pub excluded_courses: Vec<Ustr>,
+
+    /// The IDs of the courses whose links were not verified because they match the ignore file.
+    pub ignored_courses: Vec<Ustr>,
+
+    /// Whether the valid links are included in the report.
+    pub report_valid: bool,
+
+    /// The number of links that were verified, which is less than `total_links` if the
+    /// verification stopped early because the maximum number of invalid links was reached.
+    pub checked_links: usize,
+
+    /// The links that failed verification along with the outcome of the verification, sorted by
+    /// course ID, short ID, and URL. The valid links are also included if `report_valid` is set.
+    failed: Vec<(CourseLink, LinkOutcome)>,
+}
+
+impl LinkReport {
+    /// Returns whether the verification stopped early because the maximum number of invalid links
+    /// was reached, so some links were not verified.
+    pub fn stopped_early(&self) -> bool {
+        self.checked_links < self.total_links
+    }
+
+    /// Returns the number of failures of each kind.
+    pub fn summary(&self) -> LinkSummary {
+        let mut summary = LinkSummary::default();
+        for (_, outcome) in &self.failed {
+            match outcome {
+                LinkOutcome::Valid => {}
+                LinkOutcome::Invalid => summary.invalid += 1,
+                LinkOutcome::Restricted(_) => summary.restricted += 1,
+                LinkOutcome::TimedOut | LinkOutcome::Unreachable(_) => summary.unreachable += 1,
+            }
+        }
+        summary
+    }
+
+    /// Returns a message describing each link that failed verification, along with whether the
+    /// validity of the link is unknown, in which case the message is a warning.
+    fn failure_messages(&self) -> Vec<(String, bool)> {
+        self.failed
+            .iter()
+            .filter_map(|(course_link, outcome)| match outcome {
+                LinkOutcome::Valid => None,
+                LinkOutcome::Invalid => Some((
+                    format!(
+                        "Course {}, asset {} has an invalid {} link.",
+                        course_link.course_id,
+                        course_link.short_id,
+                        course_link.link.platform_name()
+                    ),
+                    false,
+                )),
+                LinkOutcome::Restricted(reason) => Some((
+                    format!(
+                        "Course {}, asset {} has a restricted {} link: {reason}.",
+                        course_link.course_id,
+                        course_link.short_id,
+                        course_link.link.platform_name()
+                    ),
+                    false,
+                )),
+                LinkOutcome::TimedOut => Some((
+                    format!(
+                        "Warning: timed out verifying the {} link of course {}, asset {}",
+                        course_link.link.platform_name(),
+                        course_link.course_id,
+                        course_link.short_id
+                    ),
+                    true,
+                )),
+                LinkOutcome::Unreachable(error) => Some((
+                    format!(
+                        "Warning: could not verify the {} link of course {}, asset {}: {error}",
+                        course_link.link.platform_name(),
+                        course_link.course_id,
+                        course_link.short_id
+                    ),
+                    true,
+                )),
+            })
+            .collect()
+    }
+
+    /// Returns a message describing each valid link, if the valid links are included in the report.
+    fn valid_messages(&self) -> Vec<String> {
+        self.failed
+            .iter()
+            .filter(|(_, outcome)| *outcome == LinkOutcome::Valid)
+            .map(|(course_link, _)| {
+                format!(
+                    "Course {}, asset {} has a valid {} link.",
+                    course_link.course_id,
+                    course_link.short_id,
+                    course_link.link.platform_name()
+                )
+            })
+            .collect()
+    }
+
+    /// Prints each valid link, if the valid links are included in the report.
+    pub fn print_valid(&self) {
+        for message in self.valid_messages() {
+            println!("{message}");
+        }
+    }
+
+    /// Prints each link that failed verification. Links whose validity is unknown are printed as
+    /// warnings.
+    pub fn print_failures(&self) {
+        for (message, is_warning) in self.failure_messages() {
+            if is_warning {
+                eprintln!("{message}");
+            } else {
+                println!("{message}");
+            }
+        }
+    }
+
+    /// Returns the report in the format used by the JSON output.
+    fn to_json(&self) -> LinkReportJson<'_> {
+        LinkReportJson {
+            total_links: self.total_links,
+            total_courses: self.total_courses,
+            invalid_links: self.summary().invalid,
+            failures: self
+                .failed
+                .iter()
+                .filter_map(|(course_link, outcome)| {
+                    Some(LinkFailure {
+                        course_id: course_link.course_id,
+                        short_id: &course_link.short_id,
+                        link: course_link.link.url(),
+                        reason: outcome.reason()?,
+                    })
+                })
+                .collect(),
+            results: self.report_valid.then(|| {
+                self.failed
+                    .iter()
+                    .map(|(course_link, outcome)| LinkResultJson {
+                        course_id: course_link.course_id,
+                        short_id: &course_link.short_id,
+                        link: course_link.link.url(),
+                        status: outcome.status(),
+                    })
+                    .collect()
+            }),
+            skipped_platforms: self
+                .skipped_platforms
+                .iter()
+                .map(|platform| platform.name())
+                .collect(),
+            excluded_courses: &self.excluded_courses,
+            ignored_courses: &self.ignored_courses,
+            stopped_early: self.stopped_early(),
+        }
+    }
+}
+
+/// Verifies all links in the transcription courses and returns the links that failed verification.
+pub(crate) fn check_links(root: &Path, options: &VerifyLinksOptions) -> Result<LinkReport> {
+    // Open the trane-transcription library in trane.
+    let trane = library::open_library(root)?;
+
+    // Go through each course and verify that all external links are valid.
+    library::warn_unknown_courses(&options.only, &trane.get_course_ids());
+    library::warn_unknown_courses(&options.exclude, &trane.get_course_ids());
+    let (links, skipped): (Vec<_>, Vec<_>) = collect_links(&trane, &library::courses_root(root)?)?
+        .into_iter()
+        .filter(|link| {
+            library::is_selected(&options.only, link.course_id)
+                && !library::is_excluded(&options.exclude, link.course_id)
+                && !ignore::is_ignored(&options.ignored, link.course_id)
+        })
+        .partition(|link| {
+            options.platforms.is_empty() || options.platforms.contains(&link.link.platform())
+        });
+    let skipped_platforms = if options.platforms.is_empty() {
+        vec![]
+    } else {
+        Platform::value_variants()
+            .iter()
+            .filter(|platform| !options.platforms.contains(platform))
+            .copied()
+            .collect()
+    };
+    let (excluded_courses, courses): (Vec<_>, Vec<_>) = library::transcription_courses(&trane)
+        .into_iter()
+        .filter(|(manifest, _)| library::is_selected(&options.only, manifest.id))
+        .map(|(manifest, _)| manifest.id)
+        .partition(|course_id| library::is_excluded(&options.exclude, *course_id));
+    let (ignored_courses, courses): (Vec<_>, Vec<_>) = courses
+        .into_iter()
+        .partition(|course_id| ignore::is_ignored(&options.ignored, *course_id));
+    let cache = Mutex::new(LinkCache::open(root)?);
+    let (failed, checked_links) = find_failed_links(&links, options, &cache);
+    cache.into_inner().unwrap().save()?;
+    Ok(LinkReport {
+        total_links: links.len(),
+        total_courses: courses.len(),
+        skipped_platforms,
+        skipped_links: skipped.len(),
+        excluded_courses,
+        ignored_courses,
+        report_valid: options.report_valid,
+        checked_links,
+        failed,
+    })
+}
+
+/// Verifies that all links in the transcription courses are valid and prints the results in the
+/// given format, unless they were already printed as JSON lines. If `output_file` is given, the
+/// results are written to it instead and only the summary is printed. Links that could not be
+/// verified because of network errors are reported as warnings. Invalid links only result in an
+/// error if `fail_on_invalid` is set in the options.
+pub(crate) fn verify_links(
+    root: &Path,
+    options: &VerifyLinksOptions,
+    format: ReportFormat,
+    output_file: Option<&Path>,
+) -> Result<()> {
+    let report = check_links(root, options)?;
+    let summary = report.summary();
+    let checked = if report.stopped_early() {
+        format!("{} of {}", report.checked_links, report.total_links)
+    } else {
+        report.total_links.to_string()
+    };
+    let mut summary_line = format!(
+        "Checked {checked} links across {} courses; {} invalid, {} restricted",
+        report.total_courses, summary.invalid, summary.restricted
+    );
+    if summary.unreachable > 0 {
+        summary_line.push_str(&format!(", {} could not be verified", summary.unreachable));
+    }
+    if !report.skipped_platforms.is_empty() {
+        let platforms = report
+            .skipped_platforms
+            .iter()
+            .map(|platform| platform.name())
+            .collect::<Vec<_>>();
+        summary_line.push_str(&format!(
+            "; skipped {} links on {}",
+            report.skipped_links,
+            platforms.join(", ")
+        ));
+    }
+    if !report.excluded_courses.is_empty() {
+        summary_line.push_str(&format!(
+            "; {} courses excluded",
+            report.excluded_courses.len()
+        ));
+    }
+    if !report.ignored_courses.is_empty() {
+        summary_line.push_str(&format!(
+            "; {} courses skipped (ignored)",
+            report.ignored_courses.len()
+        ));
+    }
+    if let (true, Some(max_failures)) = (report.stopped_early(), options.max_failures) {
+        summary_line.push_str(&format!(
+            "; stopped early after finding {max_failures} invalid links"
+        ));
+    }
+    summary_line.push('.');
+
+    if options.json_lines {
+        // The results were already printed as they were verified, and stdout only contains JSON.
+        eprintln!("{summary_line}");
+    } else if let Some(output_file) = output_file {
+        let mut contents = match format {
+            ReportFormat::Plain => {
+                let mut contents = String::new();
+                for message in report.valid_messages() {
+                    contents.push_str(&message);
+                    contents.push('\n');
+                }
+                for (message, _) in report.failure_messages() {
+                    contents.push_str(&message);
+                    contents.push('\n');
+                }
+                contents.push_str(&summary_line);
+                contents
+            }
+            ReportFormat::Json => serde_json::to_string_pretty(&report.to_json())?,
+        };
+        contents.push('\n');
+        output::write_report(&contents, Some(output_file))?;
+        status!("{summary_line}");
+    } else {
+        match format {
+            ReportFormat::Plain => {
+                ignore::report_ignored(&report.ignored_courses);
+                report.print_valid();
+                report.print_failures();
+                if summary == LinkSummary::default() {
+                    status!("All courses have valid links.");
+                }
+                status!("{summary_line}");
+            }
+            ReportFormat::Json => {
+                println!("{}", serde_json::to_string_pretty(&report.to_json())?)
+            }
+        }
+    }
+    if options.fail_on_invalid && summary.invalid > 0 {
+        bail!(Failure::new(
+            FailureKind::InvalidLinks,
+            format!("found {} invalid links", summary.invalid)
+        ));
+    }
+    exit::fail_on_warnings(
+        FailureKind::InvalidLinks,
+        &[
+            (summary.invalid, "invalid links"),
+            (summary.restricted, "restricted links"),
+            (summary.unreachable, "links that could not be verified"),
+        ],
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use anyhow::Result;
+    use std::sync::Mutex;
+    use trane::data::course_generator::transcription::TranscriptionLink;
+    use ustr::Ustr;
+
+    use super::{
+        cache_key, canonical_link, error_status, find_failed_links, normalize_youtube_link,
+        parse_youtube_video_id, youtube_link_with_range, CourseLink, ExternalLink, LinkStatus,
+        VerifyLinksOptions,
+    };
+    use crate::cache::LinkCache;
+
+    /// Verifies that the video ID is extracted from all the supported forms of YouTube links.
+    #[test]
+    fn parse_youtube_video_ids() {
+        let expected = Some("h8Pv04Tj5GE".to_string());
+        assert_eq!(
+            parse_youtube_video_id("https://www.youtube.com/watch?v=h8Pv04Tj5GE"),
+            expected
+        );
+        assert_eq!(
+            parse_youtube_video_id("https://youtube.com/watch?list=PL123&v=h8Pv04Tj5GE&t=30"),
+            expected
+        );
+        assert_eq!(
+            parse_youtube_video_id("https://youtu.be/h8Pv04Tj5GE?t=30"),
+            expected
+        );
+        assert_eq!(
+            parse_youtube_video_id("https://www.youtube.com/shorts/h8Pv04Tj5GE"),
+            expected
+        );
+        assert_eq!(
+            parse_youtube_video_id("https://www.youtube.com/embed/h8Pv04Tj5GE?start=10"),
+            expected
+        );
+        assert_eq!(
+            parse_youtube_video_id("https://youtube.com/shorts/h8Pv04Tj5GE?feature=share"),
+            expected
+        );
+        assert_eq!(
+            parse_youtube_video_id("https://www.youtube-nocookie.com/embed/h8Pv04Tj5GE"),
+            expected
+        );
+        assert_eq!(
+            parse_youtube_video_id("https://youtube-nocookie.com/embed/h8Pv04Tj5GE?start=10"),
+            expected
+        );
+    }
+
+    /// Verifies that malformed YouTube links do not produce a video ID.
+    #[test]
+    fn parse_malformed_youtube_links() {
+        assert_eq!(parse_youtube_video_id(""), None);
+        assert_eq!(parse_youtube_video_id("https://www.youtube.com/"), None);
+        assert_eq!(
+            parse_youtube_video_id("https://www.youtube.com/watch"),
+            None
+        );
+        assert_eq!(
+            parse_youtube_video_id("https://www.youtube.com/watch?t=30"),
+            None
+        );
+        assert_eq!(parse_youtube_video_id("https://youtu.be/short"), None);
+        assert_eq!(
+            parse_youtube_video_id("https://youtu.be/h8Pv04Tj5GE!"),
+            None
+        );
+        assert_eq!(
+            parse_youtube_video_id("https://example.com/watch?v=h8Pv04Tj5GE"),
+            None
+        );
+        assert_eq!(
+            parse_youtube_video_id("https://www.youtube-nocookie.com/watch?v=h8Pv04Tj5GE"),
+            None
+        );
+    }
+
+    /// Verifies that links to the same YouTube video have the same canonical form.
+    #[test]
+    fn canonical_youtube_links() {
+        let expected = "youtube:h8Pv04Tj5GE";
+        assert_eq!(canonical_link("https://youtu.be/h8Pv04Tj5GE"), expected);
+        assert_eq!(
+            canonical_link("https://youtu.be/h8Pv04Tj5GE?t=30"),
+            expected
+        );
+        assert_eq!(
+            canonical_link("https://www.youtube.com/watch?v=h8Pv04Tj5GE"),
+            expected
+        );
+        assert_eq!(
+            canonical_link("https://www.youtube.com/watch?v=h8Pv04Tj5GE&t=1m30s"),
+            expected
+        );
+        assert_eq!(
+            canonical_link("https://youtube.com/watch?feature=share&v=h8Pv04Tj5GE"),
+            expected
+        );
+        assert_eq!(
+            canonical_link("https://vimeo.com/123456"),
+            "https://vimeo.com/123456"
+        );
+    }
+
+    /// Verifies that YouTube links are rewritten to the canonical form, keeping their timestamps.
+    #[test]
+    fn normalize_youtube_links() {
+        let expected = "https://www.youtube.com/watch?v=h8Pv04Tj5GE";
+        assert_eq!(
+            normalize_youtube_link("https://youtu.be/h8Pv04Tj5GE").as_deref(),
+            Some(expected)
+        );
+        assert_eq!(
+            normalize_youtube_link("http://youtube.com/watch?feature=share&v=h8Pv04Tj5GE")
+                .as_deref(),
+            Some(expected)
+        );
+        assert_eq!(normalize_youtube_link(expected).as_deref(), Some(expected));
+        assert_eq!(
+            normalize_youtube_link("https://youtu.be/h8Pv04Tj5GE?t=1m30s").as_deref(),
+            Some("https://www.youtube.com/watch?v=h8Pv04Tj5GE&t=1m30s")
+        );
+        assert_eq!(
+            normalize_youtube_link("https://www.youtube.com/embed/h8Pv04Tj5GE?start=10&end=20")
+                .as_deref(),
+            Some("https://www.youtube.com/watch?v=h8Pv04Tj5GE&t=10&end=20")
+        );
+        assert_eq!(
+            normalize_youtube_link("https://www.youtube-nocookie.com/embed/h8Pv04Tj5GE").as_deref(),
+            Some(expected)
+        );
+        assert_eq!(
+            normalize_youtube_link("https://youtube.com/shorts/h8Pv04Tj5GE").as_deref(),
+            Some(expected)
+        );
+        assert_eq!(normalize_youtube_link("https://vimeo.com/123456"), None);
+    }
+
+    /// Verifies that the platform of a link is inferred from its URL.
+    #[test]
+    fn external_link_platform() {
+        let platform = |url: &str| {
+            ExternalLink::from(&TranscriptionLink::YouTube(url.to_string())).platform_name()
+        };
+        assert_eq!(
+            platform("https://www.youtube.com/watch?v=h8Pv04Tj5GE"),
+            "YouTube"
+        );
+        assert_eq!(platform("https://youtu.be/h8Pv04Tj5GE"), "YouTube");
+        assert_eq!(
+            platform("https://www.youtube-nocookie.com/embed/h8Pv04Tj5GE"),
+            "YouTube"
+        );
+        assert_eq!(platform("https://vimeo.com/123456"), "Vimeo");
+        assert_eq!(
+            platform("https://soundcloud.com/artist/track"),
+            "SoundCloud"
+        );
+        assert_eq!(
+            platform("https://open.spotify.com/track/4uLU6hMCjMI75M1A2tKUQC"),
+            "Spotify"
+        );
+        assert_eq!(platform("https://example.com/track.mp3"), "web");
+    }
+
+    /// Verifies that only the host of a link determines its platform, so lookalike domains and
+    /// links mentioning a platform elsewhere are not attributed to it.
+    #[test]
+    fn lookalike_link_platform() {
+        let platform = |url: &str| ExternalLink::from(url).platform_name();
+        assert_eq!(platform("https://player.vimeo.com/video/123456"), "Vimeo");
+        assert_eq!(
+            platform("https://M.YouTube.com/watch?v=h8Pv04Tj5GE"),
+            "YouTube"
+        );
+        assert_eq!(platform("https://notvimeo.com/123456"), "web");
+        assert_eq!(platform("https://vimeo.com.example.com/123456"), "web");
+        assert_eq!(platform("https://example.com/soundcloud.com/track"), "web");
+        assert_eq!(
+            platform("https://youtube.com/watch?v=h8Pv04Tj5GE&r=vimeo.com/"),
+            "YouTube"
+        );
+        assert_eq!(platform("https://vimeo.com@example.com/123456"), "web");
+        assert_eq!(platform("vimeo.com/123456"), "web");
+    }
+
+    /// Verifies that the oembed endpoint of a link depends on its platform, that YouTube links are
+    /// queried in their canonical form, and that the links are percent-encoded.
+    #[test]
+    fn oembed_urls() {
+        let oembed_url = |url: &str| {
+            ExternalLink::from(&TranscriptionLink::YouTube(url.to_string())).oembed_url()
+        };
+        assert_eq!(
+            oembed_url("https://youtu.be/h8Pv04Tj5GE?t=5").as_deref(),
+            Some(
+                "https://www.youtube.com/oembed?url=https%3A%2F%2Fwww%2Eyoutube%2Ecom%2Fwatch%3Fv\
+                %3Dh8Pv04Tj5GE&format=json"
+            )
+        );
+        assert_eq!(
+            oembed_url("https://vimeo.com/123456?h=abc&share=copy").as_deref(),
+            Some(
+                "https://vimeo.com/api/oembed.json?url=https%3A%2F%2Fvimeo%2Ecom%2F123456%3Fh%3D\
+                abc%26share%3Dcopy"
+            )
+        );
+        assert_eq!(oembed_url("https://www.youtube.com/watch?v=bad"), None);
+        assert_eq!(oembed_url("https://example.com/track.mp3"), None);
+    }
+
+    /// Verifies that the start and end of a passage are encoded in the link and validated.
+    #[test]
+    fn youtube_links_with_range() {
+        assert_eq!(
+            youtube_link_with_range("https://youtu.be/h8Pv04Tj5GE?t=5", Some(30), Some(90))
+                .unwrap(),
+            "https://www.youtube.com/watch?v=h8Pv04Tj5GE&t=30&end=90"
+        );
+        assert_eq!(
+            youtube_link_with_range(
+                "https://www.youtube.com/watch?v=h8Pv04Tj5GE",
+                None,
+                Some(90)
+            )
+            .unwrap(),
+            "https://www.youtube.com/watch?v=h8Pv04Tj5GE&end=90"
+        );
+        assert_eq!(
+            parse_youtube_video_id("https://www.youtube.com/watch?v=h8Pv04Tj5GE&t=30&end=90"),
+            Some("h8Pv04Tj5GE".to_string())
+        );
+        assert!(
+            youtube_link_with_range("https://youtu.be/h8Pv04Tj5GE", Some(90), Some(30)).is_err()
+        );
+        assert!(youtube_link_with_range("https://vimeo.com/76979871", Some(30), None).is_err());
+    }
+
+    /// Verifies that the results of head-only verifications and of those checking the age
+    /// restriction are cached separately from those of the other verifications.
+    #[test]
+    fn cache_keys() {
+        let link = |url: &str| ExternalLink::from(&TranscriptionLink::YouTube(url.to_string()));
+        let full = VerifyLinksOptions::default();
+        let head_only = VerifyLinksOptions {
+            head_only: true,
+            ..Default::default()
+        };
+        let youtube = link("https://youtu.be/h8Pv04Tj5GE");
+        assert_eq!(cache_key(&youtube, &full), "https://youtu.be/h8Pv04Tj5GE");
+        assert_eq!(
+            cache_key(&youtube, &head_only),
+            "head-only:https://youtu.be/h8Pv04Tj5GE"
+        );
+        let age_restriction = VerifyLinksOptions {
+            check_age_restriction: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            cache_key(&youtube, &age_restriction),
+            "age-restriction:https://youtu.be/h8Pv04Tj5GE"
+        );
+        let vimeo = link("https://vimeo.com/123456");
+        assert_eq!(cache_key(&vimeo, &head_only), "https://vimeo.com/123456");
+        assert_eq!(
+            cache_key(&vimeo, &age_restriction),
+            "https://vimeo.com/123456"
+        );
+    }
+
+    /// Verifies that the verification only reports an early stop if the failure limit was reached
+    /// before every link was checked.
+    #[test]
+    fn stop_after_max_failures() -> Result<()> {
+        // Malformed YouTube links are reported as not found without sending any request.
+        let links = ["a", "b"]
+            .into_iter()
+            .map(|short_id| CourseLink {
+                course_id: Ustr::from("trane::transcription::a"),
+                short_id: short_id.to_string(),
+                link: ExternalLink::YouTube("https://www.youtube.com/watch?v=bad".to_string()),
+            })
+            .collect::<Vec<_>>();
+        let temp_dir = tempfile::tempdir()?;
+        let cache = Mutex::new(LinkCache::open(temp_dir.path())?);
+        let options = |max_failures| VerifyLinksOptions {
+            jobs: 1,
+            use_cache: false,
+            progress: false,
+            max_failures: Some(max_failures),
+            ..Default::default()
+        };
+
+        let (failed, checked) = find_failed_links(&links, &options(1), &cache);
+        assert_eq!(failed.len(), 1);
+        assert_eq!(checked, 1);
+
+        let (failed, checked) = find_failed_links(&links, &options(2), &cache);
+        assert_eq!(failed.len(), 2);
+        assert_eq!(checked, 2);
+        Ok(())
+    }
+
+    /// Verifies that the failed links of the same passage are sorted by URL, regardless of the
+    /// order in which they were checked.
+    #[test]
+    fn sort_failed_links() -> Result<()> {
+        let links = ["bad2", "bad1"]
+            .into_iter()
+            .map(|id| CourseLink {
+                course_id: Ustr::from("trane::transcription::a"),
+                short_id: "a".to_string(),
+                link: ExternalLink::YouTube(format!("https://www.youtube.com/watch?v={id}")),
+            })
+            .collect::<Vec<_>>();
+        let temp_dir = tempfile::tempdir()?;
+        let cache = Mutex::new(LinkCache::open(temp_dir.path())?);
+        let options = VerifyLinksOptions {
+            jobs: 2,
+            use_cache: false,
+            progress: false,
+            ..Default::default()
+        };
+
+        let (failed, _) = find_failed_links(&links, &options, &cache);
+        assert_eq!(
+            failed
+                .iter()
+                .map(|(link, _)| link.link.url())
+                .collect::<Vec<_>>(),
+            vec![
+                "https://www.youtube.com/watch?v=bad1",
+                "https://www.youtube.com/watch?v=bad2"
+            ]
+        );
+        Ok(())
+    }
+
+    /// Verifies that client errors mark the link as not found, while server errors, rate limiting,
+    /// and request timeouts are network errors.
+    #[test]
+    fn error_statuses() {
+        let status = |code| {
+            let response = ureq::Response::new(code, "", "").unwrap();
+            error_status(ureq::Error::Status(code, response))
+        };
+        assert_eq!(status(404), LinkStatus::NotFound);
+        assert_eq!(status(410), LinkStatus::NotFound);
+        assert!(matches!(
+            status(429),
+            LinkStatus::NetworkError {
+                timed_out: false,
+                ..
+            }
+        ));
+        assert!(matches!(
+            status(408),
+            LinkStatus::NetworkError {
+                timed_out: true,
+                ..
+            }
+        ));
+        assert!(matches!(
+            status(503),
+            LinkStatus::NetworkError {
+                timed_out: false,
+                ..
+            }
+        ));
+    }
+}
@@ -0,0 +1,324 @@
+//! Contains the `lint` subcommand, which checks that the course manifests follow the conventions of
+//! this repository. Each convention is a rule, and adding a new one only requires adding it to the
+//! list returned by `lint_rules`.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Result};
+use trane::data::CourseManifest;
+use ustr::Ustr;
+
+use crate::{
+    exit::{self, Failure, FailureKind},
+    graph::DependencyGraph,
+    ignore, library,
+    output::{self, status, Color},
+};
+
+/// The values of the `course_series` metadata key allowed by default.
+pub(crate) const KNOWN_COURSE_SERIES: &[&str] =
+    &["trane_transcription", "trane_transcription_advanced"];
+
+/// The information available to a lint rule when checking a course.
+pub(crate) struct LintContext<'a> {
+    /// The path to the courses directory.
+    pub courses_root: &'a Path,
+
+    /// The path to the manifest of the course.
+    pub path: &'a Path,
+
+    /// The manifest of the course.
+    pub manifest: &'a CourseManifest,
+
+    /// The values allowed in the `course_series` metadata key.
+    pub known_course_series: &'a [String],
+
+    /// The dependency graph of all the courses in the library.
+    pub graph: &'a DependencyGraph,
+}
+
+/// A function that checks a course and returns a description of the violation of a rule, if any.
+type LintCheck = Box<dyn Fn(&LintContext) -> Option<String>>;
+
+/// How a violation of a lint rule is treated.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Severity {
+    /// The violation is reported and makes the lint fail.
+    Error,
+
+    /// The violation is reported, but does not make the lint fail.
+    Warning,
+}
+
+/// A rule that every course manifest must follow.
+pub(crate) struct LintRule {
+    /// The name of the rule, printed along with every violation.
+    pub name: &'static str,
+
+    /// How violations of the rule are treated.
+    pub severity: Severity,
+
+    /// Checks the course and returns a description of the violation, if any.
+    pub check: LintCheck,
+}
+
+/// Returns all the lint rules.
+fn lint_rules() -> Vec<LintRule> {
+    vec![
+        LintRule {
+            name: "trane-project-author",
+            severity: Severity::Error,
+            check: Box::new(|context| {
+                let authors = context.manifest.authors.as_deref().unwrap_or_default();
+                if authors.iter().any(|author| author == "The Trane Project") {
+                    None
+                } else {
+                    Some("authors do not include The Trane Project".to_string())
+                }
+            }),
+        },
+        LintRule {
+            name: "course-series-metadata",
+            severity: Severity::Error,
+            check: Box::new(|context| {
+                let has_series = context
+                    .manifest
+                    .metadata
+                    .as_ref()
+                    .is_some_and(|metadata| metadata.contains_key("course_series"));
+                if has_series {
+                    None
+                } else {
+                    Some("metadata does not include the course_series key".to_string())
+                }
+            }),
+        },
+        LintRule {
+            name: "known-course-series",
+            severity: Severity::Error,
+            check: Box::new(|context| {
+                let series = context.manifest.metadata.as_ref()?.get("course_series")?;
+                let unknown = series
+                    .iter()
+                    .filter(|tag| !context.known_course_series.contains(tag))
+                    .map(String::as_str)
+                    .collect::<Vec<_>>();
+                if unknown.is_empty() {
+                    None
+                } else {
+                    Some(format!(
+                        "unknown course_series tags: {}",
+                        unknown.join(", ")
+                    ))
+                }
+            }),
+        },
+        LintRule {
+            name: "id-matches-path",
+            severity: Severity::Error,
+            check: Box::new(|context| {
+                let id = context.manifest.id;
+                if id.is_empty() {
+                    return Some("id is empty".to_string());
+                }
+                let expected = library::manifest_path(context.courses_root, &id);
+                if expected == context.path {
+                    None
+                } else {
+                    Some(format!(
+                        "expected manifest at {}, found at {}",
+                        expected.display(),
+                        context.path.display()
+                    ))
+                }
+            }),
+        },
+        LintRule {
+            name: "skip-flags-combination",
+            severity: Severity::Warning,
+            check: Box::new(|context| {
+                let config = library::transcription_config(context.manifest)?;
+                if config.skip_singing_lessons && config.skip_advanced_lessons {
+                    Some(
+                        "both skip_singing_lessons and skip_advanced_lessons are set, so the \
+                        course generates almost no exercises"
+                            .to_string(),
+                    )
+                } else {
+                    None
+                }
+            }),
+        },
+        LintRule {
+            name: "isolated-course",
+            severity: Severity::Warning,
+            check: Box::new(|context| {
+                library::transcription_config(context.manifest)?;
+                if context.graph.is_isolated(context.manifest.id) {
+                    Some(
+                        "the course has no dependencies and no other course depends on it"
+                            .to_string(),
+                    )
+                } else {
+                    None
+                }
+            }),
+        },
+    ]
+}
+
+/// A violation of a lint rule found in a course.
+pub(crate) struct Violation {
+    /// The ID of the course.
+    pub course_id: Ustr,
+
+    /// The name of the violated rule.
+    pub rule: &'static str,
+
+    /// How the violation is treated.
+    pub severity: Severity,
+
+    /// The description of the violation.
+    pub message: String,
+}
+
+/// Checks the course manifests against the given rules and returns the violations found, in the
+/// order of the manifests. The courses matching the `ignored` patterns are skipped, but are still
+/// part of the dependency graph, so that the courses depending on them are not reported as
+/// isolated.
+fn find_violations(
+    courses_root: &Path,
+    manifests: &[(PathBuf, CourseManifest)],
+    rules: &[LintRule],
+    known_course_series: &[String],
+    ignored: &[Ustr],
+) -> Vec<Violation> {
+    let graph = DependencyGraph::from_manifests(manifests.iter().map(|(_, manifest)| manifest));
+    let mut violations = vec![];
+    for (path, manifest) in manifests {
+        if ignore::is_ignored(ignored, manifest.id) {
+            continue;
+        }
+        let context = LintContext {
+            courses_root,
+            path,
+            manifest,
+            known_course_series,
+            graph: &graph,
+        };
+        for rule in rules {
+            if let Some(message) = (rule.check)(&context) {
+                violations.push(Violation {
+                    course_id: manifest.id,
+                    rule: rule.name,
+                    severity: rule.severity,
+                    message,
+                });
+            }
+        }
+    }
+    violations
+}
+
+/// Checks every course manifest against all the lint rules and returns the number of violations
+/// with error and warning severity, without printing them. The courses matching the `ignored`
+/// patterns are skipped.
+pub(crate) fn count_violations(
+    root: &Path,
+    known_course_series: &[String],
+    ignored: &[Ustr],
+) -> Result<(usize, usize)> {
+    let courses_root = library::courses_root(root)?;
+    let manifests = library::read_all_manifests(&courses_root)?;
+    let violations = find_violations(
+        &courses_root,
+        &manifests,
+        &lint_rules(),
+        known_course_series,
+        ignored,
+    );
+    let errors = violations
+        .iter()
+        .filter(|violation| violation.severity == Severity::Error)
+        .count();
+    Ok((errors, violations.len() - errors))
+}
+
+/// Checks every course manifest against all the lint rules, except those in `allowed`, and fails if
+/// any rule with error severity is violated. `known_course_series` lists the values allowed in the
+/// `course_series` metadata key. The courses matching the `ignored` patterns are skipped.
+pub(crate) fn lint(
+    root: &Path,
+    allowed: &[String],
+    known_course_series: &[String],
+    ignored: &[Ustr],
+) -> Result<()> {
+    let courses_root = library::courses_root(root)?;
+    let rules = lint_rules();
+    for name in allowed {
+        if !rules.iter().any(|rule| rule.name == name) {
+            bail!(Failure::new(
+                FailureKind::Usage,
+                format!("unknown lint rule {name}")
+            ));
+        }
+    }
+    let rules = rules
+        .into_iter()
+        .filter(|rule| !allowed.iter().any(|name| name == rule.name))
+        .collect::<Vec<_>>();
+
+    let manifests = library::read_all_manifests(&courses_root)?;
+    let ignored_courses = manifests
+        .iter()
+        .map(|(_, manifest)| manifest.id)
+        .filter(|course_id| ignore::is_ignored(ignored, *course_id))
+        .collect::<Vec<_>>();
+    ignore::report_ignored(&ignored_courses);
+
+    let mut violations = 0;
+    let mut warnings = 0;
+    for violation in find_violations(
+        &courses_root,
+        &manifests,
+        &rules,
+        known_course_series,
+        ignored,
+    ) {
+        match violation.severity {
+            Severity::Error => {
+                violations += 1;
+                println!(
+                    "Course {}: {}: {}",
+                    violation.course_id,
+                    output::paint(violation.rule, Color::Red),
+                    violation.message
+                );
+            }
+            Severity::Warning => {
+                warnings += 1;
+                println!(
+                    "Course {}: {}: {}: {}",
+                    violation.course_id,
+                    output::paint("warning", Color::Yellow),
+                    violation.rule,
+                    violation.message
+                );
+            }
+        }
+    }
+
+    if violations > 0 {
+        bail!(Failure::new(
+            FailureKind::InvalidCourses,
+            format!("found {violations} lint violations")
+        ));
+    }
+    exit::fail_on_warnings(FailureKind::InvalidCourses, &[(warnings, "lint warnings")])?;
+    if warnings > 0 {
+        status!("All courses follow the conventions, with {warnings} warnings.");
+    } else {
+        status!("All courses follow the conventions.");
+    }
+    Ok(())
+}
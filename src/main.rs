@@ -1,164 +1,917 @@
 //! Command line tool with utilities to make working with the courses in this repository easier.
 
-use std::{collections::BTreeMap, fs, vec};
+mod archive;
+mod bench;
+mod cache;
+mod changes;
+mod checks;
+mod config;
+mod dedupe;
+mod dependencies;
+mod diff;
+mod doctor;
+mod exit;
+mod fix;
+mod git;
+mod graph;
+mod ignore;
+mod index;
+mod library;
+mod links;
+mod lint;
+mod orphans;
+mod output;
+mod passages;
+mod prompt;
+mod reports;
+mod schema;
+mod search;
+mod summary;
+mod templates;
+mod touch;
+mod tree;
+
+use std::{
+    collections::BTreeMap,
+    fs,
+    io::{self, BufRead, IsTerminal, Write},
+    path::{Path, PathBuf},
+    process,
+    time::Duration,
+    vec,
+};
 
 use anyhow::{bail, Context, Result};
-use clap::{Parser, Subcommand};
-use serde::ser::Serialize;
+use clap::{ArgAction, CommandFactory, Parser, Subcommand};
+use clap_complete::Shell;
+use log::LevelFilter;
+use serde::Serialize;
+use similar::TextDiff;
 use trane::{
-    course_library::CourseLibrary,
     data::{
-        course_generator::transcription::{
-            TranscriptionAsset, TranscriptionConfig, TranscriptionLink,
-        },
-        CourseGenerator, CourseManifestBuilder,
+        course_generator::transcription::{TranscriptionConfig, TranscriptionLink},
+        CourseGenerator, CourseManifest, CourseManifestBuilder,
     },
     Trane,
 };
 use ustr::Ustr;
 
-/// Creates a new course with the basic details filled in.
-fn create_course(id: &str) -> Result<()> {
-    // Check the required courses are available.
-    let root = std::env::current_dir()?.join("courses");
-    if !root.exists() {
-        bail!("courses directory does not exist at {}", root.display());
-    }
-    let directory = if id.starts_with("trane::transcription::") {
-        let path = id
-            .trim_start_matches("trane::transcription::")
-            .split("::")
-            .collect::<Vec<_>>()
-            .join("/");
-        root.join(path)
-    } else {
-        let path = id.split("::").collect::<Vec<_>>().join("/");
-        root.join(path)
-    };
-    if directory.exists() {
-        bail!("course already exists at {}", directory.display());
+use crate::{
+    changes::FileChanges,
+    checks::CourseCheck,
+    config::{Config, CONFIG_FILE},
+    doctor::DoctorChecks,
+    exit::{Failure, FailureKind},
+    graph::{DependencyGraph, GraphFormat},
+    library::MANIFEST_FILE,
+    links::{Platform, VerifyLinksOptions},
+    output::{status, Color, ColorChoice},
+    passages::NewPassage,
+    reports::{OutputFormat, ReportFormat},
+    templates::CourseTemplate,
+};
+
+/// The options used to fill in the manifest of a new course.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct NewCourseOptions {
+    /// The name of the course. Left unset if not given.
+    name: Option<String>,
+
+    /// The description of the course. Left unset if not given.
+    description: Option<String>,
+
+    /// The authors of the course.
+    authors: Vec<String>,
+
+    /// The values of the `course_series` metadata key.
+    course_series: Vec<String>,
+
+    /// The transcription dependencies of the course.
+    dependencies: Vec<Ustr>,
+
+    /// Whether to skip the singing lessons.
+    skip_singing_lessons: bool,
+
+    /// Whether to skip the advanced lessons.
+    skip_advanced_lessons: bool,
+}
+
+impl Default for NewCourseOptions {
+    fn default() -> Self {
+        Self {
+            name: None,
+            description: None,
+            authors: vec!["The Trane Project".to_string()],
+            course_series: vec!["trane_transcription".to_string()],
+            dependencies: vec![],
+            skip_singing_lessons: false,
+            skip_advanced_lessons: false,
+        }
+    }
+}
+
+impl NewCourseOptions {
+    /// Asks the user for each of the options, using the current values as the defaults.
+    fn prompt(self, input: &mut impl BufRead, output: &mut impl Write) -> Result<Self> {
+        let name = prompt::prompt_optional(input, output, "Name", self.name.as_deref())?;
+        let description =
+            prompt::prompt_optional(input, output, "Description", self.description.as_deref())?;
+        let authors = prompt::prompt_list(input, output, "Authors", &self.authors)?;
+        let course_series =
+            prompt::prompt_list(input, output, "Course series", &self.course_series)?;
+        let dependencies = self
+            .dependencies
+            .iter()
+            .map(|id| id.to_string())
+            .collect::<Vec<_>>();
+        let dependencies = prompt::prompt_list(input, output, "Dependencies", &dependencies)?
+            .iter()
+            .map(|id| library::normalize_course_id(id))
+            .collect();
+        let skip_singing_lessons = prompt::prompt_bool(
+            input,
+            output,
+            "Skip singing lessons?",
+            self.skip_singing_lessons,
+        )?;
+        let skip_advanced_lessons = prompt::prompt_bool(
+            input,
+            output,
+            "Skip advanced lessons?",
+            self.skip_advanced_lessons,
+        )?;
+        Ok(Self {
+            name,
+            description,
+            authors,
+            course_series,
+            dependencies,
+            skip_singing_lessons,
+            skip_advanced_lessons,
+        })
     }
+}
 
-    // Generate the course manifest with the required fields filled in.
-    let course_id = if id.starts_with("trane::transcription::") {
-        Ustr::from(id)
-    } else {
-        Ustr::from(&format!("trane::transcription::{id}"))
+/// Builds the manifest of a new course from the given template, with the required fields filled in
+/// from the given options.
+fn build_manifest(
+    id: Ustr,
+    template: CourseTemplate,
+    options: NewCourseOptions,
+) -> Result<CourseManifest> {
+    let config = TranscriptionConfig {
+        transcription_dependencies: options.dependencies,
+        skip_singing_lessons: options.skip_singing_lessons,
+        skip_advanced_lessons: options.skip_advanced_lessons,
+        ..template.config()
     };
-    let course_manifest = CourseManifestBuilder::default()
-        .id(course_id)
-        .authors(Some(vec!["The Trane Project".to_string()]))
+    let mut builder = CourseManifestBuilder::default();
+    builder
+        .id(id)
+        .description(options.description)
+        .authors(Some(options.authors))
         .metadata(Some(BTreeMap::from([(
             "course_series".to_string(),
-            vec!["trane_transcription".to_string()],
+            options.course_series,
         )])))
-        .generator_config(Some(CourseGenerator::Transcription(TranscriptionConfig {
-            transcription_dependencies: vec![],
-            passage_directory: "".to_string(),
-            inlined_passages: vec![],
-            skip_singing_lessons: false,
-            skip_advanced_lessons: false,
-        })))
+        .generator_config(Some(CourseGenerator::Transcription(config)));
+    if let Some(name) = options.name {
+        builder.name(name);
+    }
+    builder
         .build()
-        .with_context(|| "failed to build course manifest")?;
+        .with_context(|| "failed to build course manifest")
+}
+
+/// The course created by the `new` subcommand.
+#[derive(Debug, Serialize)]
+struct CreatedCourse {
+    /// The ID of the course.
+    id: Ustr,
+
+    /// The directory of the course.
+    path: PathBuf,
+
+    /// The manifest of the course.
+    #[serde(skip)]
+    manifest: CourseManifest,
+}
+
+/// The course the `new` subcommand would create, as printed in dry-run mode with the JSON format.
+#[derive(Debug, Serialize)]
+struct PlannedCourse<'a> {
+    /// The action that would be taken.
+    action: &'static str,
+
+    /// The ID of the course.
+    id: Ustr,
+
+    /// The directory of the course.
+    path: &'a Path,
+
+    /// The manifest that would be written.
+    manifest: &'a CourseManifest,
+}
+
+impl CreatedCourse {
+    /// Prints the course in the given format. In dry-run mode, the JSON format describes the
+    /// planned action along with the full manifest.
+    fn print(&self, format: ReportFormat, dry_run: bool) -> Result<()> {
+        match format {
+            ReportFormat::Plain => {
+                status!("Created course {} at {}", self.id, self.path.display())
+            }
+            ReportFormat::Json if dry_run => {
+                let plan = PlannedCourse {
+                    action: "create",
+                    id: self.id,
+                    path: &self.path,
+                    manifest: &self.manifest,
+                };
+                println!("{}", serde_json::to_string_pretty(&plan)?)
+            }
+            ReportFormat::Json => println!("{}", serde_json::to_string_pretty(self)?),
+        }
+        Ok(())
+    }
+}
+
+/// Creates a new course from the given template with the basic details filled in from the given
+/// options. If `interactive` is true, the user is asked for the details of the course, using the
+/// options as the defaults. In dry-run mode with the JSON format, the changes are not printed,
+/// since the course is printed as a plan instead.
+fn create_course(
+    root: &Path,
+    changes: FileChanges,
+    id: &str,
+    template: CourseTemplate,
+    mut options: NewCourseOptions,
+    interactive: bool,
+    format: ReportFormat,
+) -> Result<CreatedCourse> {
+    // Check that the ID is well-formed before anything is written.
+    let normalized_id = library::normalize_course_id(id);
+    if let Some(segment) = checks::invalid_course_id_segment(&normalized_id) {
+        bail!(Failure::new(
+            FailureKind::Usage,
+            format!(
+                "invalid course id {normalized_id}: segment \"{segment}\" must contain only \
+                lowercase letters, digits, and underscores"
+            )
+        ));
+    }
+
+    // Check the required courses are available.
+    let courses_root = library::courses_root(root)?;
+    let directory = library::course_directory(&courses_root, id);
+    if directory.exists() {
+        bail!("course already exists at {}", directory.display());
+    }
+
+    // Collect the details of the course and warn about dependencies that do not exist yet.
+    if interactive {
+        options = options.prompt(&mut io::stdin().lock(), &mut io::stdout())?;
+    }
+    let mut missing_dependencies = 0;
+    for dependency in &options.dependencies {
+        let dependency_directory = library::course_directory(&courses_root, dependency);
+        if !dependency_directory.join(MANIFEST_FILE).exists() {
+            missing_dependencies += 1;
+            eprintln!(
+                "Warning: dependency {dependency} does not exist at {}",
+                dependency_directory.display()
+            );
+        }
+    }
+    exit::fail_on_warnings(
+        FailureKind::InvalidCourses,
+        &[(missing_dependencies, "missing dependencies")],
+    )?;
 
     // Create the directory and write the course manifest.
-    fs::create_dir_all(&directory).with_context(|| {
-        format!(
-            "failed to create course directory at {}",
-            directory.display()
-        )
-    })?;
-    let formatter = serde_json::ser::PrettyFormatter::with_indent(b"    ");
-    let mut buf = Vec::new();
-    let mut ser = serde_json::Serializer::with_formatter(&mut buf, formatter);
-    course_manifest
-        .serialize(&mut ser)
-        .with_context(|| "failed to serialize repository metadata")?;
-    let manifest_path = directory.join("course_manifest.json");
-    fs::write(&manifest_path, buf).with_context(|| {
-        format!(
-            "failed to write course metadata to {}",
-            manifest_path.display()
-        )
-    })?;
-    Ok(())
+    let course_manifest = build_manifest(normalized_id, template, options)?;
+    if !(changes.dry_run && format == ReportFormat::Json) {
+        changes.create_dir_all(&directory)?;
+        changes.write_manifest(&directory.join(MANIFEST_FILE), &course_manifest)?;
+    }
+    Ok(CreatedCourse {
+        id: course_manifest.id,
+        path: directory,
+        manifest: course_manifest,
+    })
 }
 
-/// Verifies that all transcription courses are valid.
-fn verify_courses() -> Result<()> {
-    // Open the trane-transcription library in trane. This requires that the command is run in the
-    // root of the repository.
-    let _ = Trane::new_local(&std::env::current_dir()?, &std::env::current_dir()?)?;
+/// Prints a unified diff between the manifest of the course and the manifest `New` would generate
+/// for it today, keeping its ID, passages, and dependencies. Both manifests are serialized with the
+/// canonical formatting, so only differences in their contents are shown.
+fn check_course(root: &Path, id: &str) -> Result<()> {
+    let courses_root = library::courses_root(root)?;
+    let manifest_path = library::manifest_path(&courses_root, id);
+    if !manifest_path.exists() {
+        bail!("course does not exist at {}", manifest_path.display());
+    }
+    let manifest = library::read_manifest(&manifest_path)?;
+    let Some(config) = library::transcription_config(&manifest) else {
+        bail!("course {} is not a transcription course", manifest.id);
+    };
+
+    // Regenerate the manifest with the default options and the data specific to the course.
+    let options = NewCourseOptions {
+        dependencies: config.transcription_dependencies.clone(),
+        ..Default::default()
+    };
+    let mut generated = build_manifest(manifest.id, CourseTemplate::default(), options)?;
+    if let Some(CourseGenerator::Transcription(generated_config)) = &mut generated.generator_config
+    {
+        generated_config.passage_directory = config.passage_directory.clone();
+        generated_config.inlined_passages = config.inlined_passages.clone();
+    }
+
+    let current = String::from_utf8(library::serialize_manifest(&manifest)?)?;
+    let generated = String::from_utf8(library::serialize_manifest(&generated)?)?;
+    if current == generated {
+        status!("Course {} matches the generated manifest.", manifest.id);
+        return Ok(());
+    }
+    let diff = TextDiff::from_lines(&current, &generated);
+    let diff = diff
+        .unified_diff()
+        .header(&manifest_path.display().to_string(), "generated")
+        .to_string();
+    for line in diff.lines() {
+        match line.chars().next() {
+            Some('+') => println!("{}", output::paint(line, Color::Green)),
+            Some('-') => println!("{}", output::paint(line, Color::Red)),
+            _ => println!("{line}"),
+        }
+    }
     Ok(())
 }
 
-/// Verifies that a YouTube link refers to a valid video.
-fn verify_youtube_link(link: &str) -> Result<()> {
-    // Use the oembed format to retrieve a small amount of data.
-    let url = format!("https://www.youtube.com/oembed?url={link}&format=json");
-    let res = ureq::get(&url)
-        .set("Example-Header", "header value")
-        .call()?;
-    if res.status() != 200 {
-        bail!("Invalid YouTube link: {}", link);
+/// Reads the course IDs from the file, one per line, ignoring blank lines and lines starting with
+/// `#`.
+fn read_course_ids(path: &Path) -> Result<Vec<String>> {
+    let contents = fs::read_to_string(path)
+        .with_context(|| format!("failed to read course IDs from {}", path.display()))?;
+    Ok(contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(str::to_string)
+        .collect())
+}
+
+/// Creates a course for each of the IDs in the file, using the same template and options for all of
+/// them. Courses that already exist are skipped, and errors creating a course do not stop the rest
+/// of the courses from being created.
+fn create_courses_from_file(
+    root: &Path,
+    changes: FileChanges,
+    path: &Path,
+    template: CourseTemplate,
+    options: &NewCourseOptions,
+) -> Result<()> {
+    let courses_root = library::courses_root(root)?;
+    let mut created = 0;
+    let mut skipped = 0;
+    let mut errors = vec![];
+    for id in read_course_ids(path)? {
+        if library::course_directory(&courses_root, &id).exists() {
+            skipped += 1;
+            status!("Skipped course {id} because it already exists");
+            continue;
+        }
+        match create_course(
+            root,
+            changes,
+            &id,
+            template,
+            options.clone(),
+            false,
+            ReportFormat::Plain,
+        ) {
+            Ok(course) => {
+                created += 1;
+                course.print(ReportFormat::Plain, changes.dry_run)?;
+            }
+            Err(e) => errors.push((id, e)),
+        }
+    }
+
+    status!("Created {created} courses, skipped {skipped} existing courses");
+    if !errors.is_empty() {
+        for (id, e) in &errors {
+            eprintln!("Failed to create course {id}: {e:#}");
+        }
+        bail!("failed to create {} courses", errors.len());
     }
     Ok(())
 }
 
-/// Verifies that all links in the transcription courses are valid.
-fn verify_links() -> Result<()> {
-    // Open the trane-transcription library in trane. This requires that the command is run in the
-    // root of the repository.
-    let trane = Trane::new_local(&std::env::current_dir()?, &std::env::current_dir()?)?;
+/// Renames the course with ID `old_id` to `new_id`, moving its directory to the one matching the
+/// new ID and updating the transcription dependencies of every course that depended on the old ID.
+/// The rename is refused if the directory of the course contains other courses.
+fn rename_course(root: &Path, changes: FileChanges, old_id: &str, new_id: &str) -> Result<()> {
+    // Check that the course exists and that the new location is free.
+    let courses_root = library::courses_root(root)?;
+    let old_directory = library::course_directory(&courses_root, old_id);
+    let new_directory = library::course_directory(&courses_root, new_id);
+    if !old_directory.join(MANIFEST_FILE).exists() {
+        bail!("course does not exist at {}", old_directory.display());
+    }
+    if new_directory.exists() {
+        bail!("course already exists at {}", new_directory.display());
+    }
+
+    // Moving the directory would also move the courses nested under it without updating their IDs.
+    let nested = library::nested_manifests(&old_directory)?;
+    if !nested.is_empty() {
+        println!("The directory of course {old_id} contains other courses:");
+        for path in &nested {
+            println!("  {}", path.display());
+        }
+        bail!("cannot rename course {old_id} because its directory contains other courses");
+    }
+
+    // Move the course directory and update the ID in its manifest. The manifest is read before the
+    // move so that it can be found in dry-run mode, when the directory is not moved.
+    let old_id = library::normalize_course_id(old_id);
+    let new_id = library::normalize_course_id(new_id);
+    let mut manifest = library::read_manifest(&old_directory.join(MANIFEST_FILE))?;
+    manifest.id = new_id;
+    if let Some(parent) = new_directory.parent() {
+        changes.create_dir_all(parent)?;
+    }
+    changes.rename(&old_directory, &new_directory)?;
+    changes.write_manifest(&new_directory.join(MANIFEST_FILE), &manifest)?;
 
-    // Go through each course and verify that all external links are valid.
-    let courses = trane.get_course_ids();
-    let mut invalid_links = 0;
-    for course_id in courses {
-        let manifest = trane.get_course_manifest(course_id).unwrap();
-        if manifest.generator_config.is_none() {
+    // Update the references to the old ID in the dependencies of all the other courses.
+    for manifest_path in library::find_manifests(&courses_root)? {
+        let mut manifest = library::read_manifest(&manifest_path)?;
+        let Some(CourseGenerator::Transcription(config)) = &mut manifest.generator_config else {
             continue;
+        };
+        if !config.transcription_dependencies.contains(&old_id) {
+            continue;
+        }
+        for dependency in &mut config.transcription_dependencies {
+            if *dependency == old_id {
+                *dependency = new_id;
+            }
         }
+        changes.write_manifest(&manifest_path, &manifest)?;
+        status!("Updated the dependencies of course {}", manifest.id);
+    }
+    Ok(())
+}
 
-        if let CourseGenerator::Transcription(config) = manifest.generator_config.unwrap() {
-            for passages in config.inlined_passages {
-                match passages.asset {
-                    TranscriptionAsset::Track {
-                        short_id,
-                        external_link,
-                        ..
-                    } => {
-                        if let Some(link) = external_link {
-                            match link {
-                                TranscriptionLink::YouTube(yt_link) => {
-                                    let valid = verify_youtube_link(&yt_link);
-                                    if valid.is_err() {
-                                        invalid_links += 1;
-                                        println!(
-                                            "Course {}, asset {} has an invalid YouTube link.",
-                                            course_id, short_id
-                                        );
-                                    }
-                                }
-                            }
-                        }
-                    }
+/// Moves every course whose ID starts with `from_prefix` under `to_prefix`, updating the IDs of the
+/// moved courses and the transcription dependencies of all the courses in a single pass. Every
+/// destination is checked before any course is moved.
+fn move_courses(
+    root: &Path,
+    changes: FileChanges,
+    from_prefix: &str,
+    to_prefix: &str,
+) -> Result<()> {
+    // Compute the new ID of every course under the old prefix. A prefix only matches whole
+    // segments of the ID.
+    let courses_root = library::courses_root(root)?;
+    let from_prefix = library::normalize_course_id(from_prefix);
+    let to_prefix = library::normalize_course_id(to_prefix);
+    let mut manifests = library::read_all_manifests(&courses_root)?;
+    let new_ids = manifests
+        .iter()
+        .filter_map(|(_, manifest)| {
+            let rest = manifest.id.strip_prefix(from_prefix.as_str())?;
+            if !rest.is_empty() && !rest.starts_with("::") {
+                return None;
+            }
+            Some((manifest.id, Ustr::from(&format!("{to_prefix}{rest}"))))
+        })
+        .collect::<BTreeMap<_, _>>();
+    if new_ids.is_empty() {
+        bail!("no courses found with prefix {from_prefix}");
+    }
+
+    // Check that every destination is free and that no moved course contains another, since
+    // moving the outer course would also move the inner one.
+    for (old_id, new_id) in &new_ids {
+        let old_directory = library::course_directory(&courses_root, old_id);
+        let new_directory = library::course_directory(&courses_root, new_id);
+        if new_directory.exists() {
+            bail!("course already exists at {}", new_directory.display());
+        }
+        for other_id in new_ids.keys() {
+            let other_directory = library::course_directory(&courses_root, other_id);
+            if other_id != old_id && other_directory.starts_with(&old_directory) {
+                bail!("cannot move course {old_id} because it contains course {other_id}");
+            }
+        }
+    }
+
+    // Move the course directories.
+    for (old_id, new_id) in &new_ids {
+        let old_directory = library::course_directory(&courses_root, old_id);
+        let new_directory = library::course_directory(&courses_root, new_id);
+        if let Some(parent) = new_directory.parent() {
+            changes.create_dir_all(parent)?;
+        }
+        changes.rename(&old_directory, &new_directory)?;
+    }
+
+    // Update the IDs of the moved courses and the dependencies of every course.
+    for (path, manifest) in &mut manifests {
+        let mut changed = false;
+        if let Some(new_id) = new_ids.get(&manifest.id) {
+            manifest.id = *new_id;
+            *path = library::manifest_path(&courses_root, new_id);
+            changed = true;
+        }
+        if let Some(CourseGenerator::Transcription(config)) = &mut manifest.generator_config {
+            for dependency in &mut config.transcription_dependencies {
+                if let Some(new_id) = new_ids.get(dependency) {
+                    *dependency = *new_id;
+                    changed = true;
                 }
             }
         }
+        if changed {
+            changes.write_manifest(path, manifest)?;
+        }
+    }
+    status!(
+        "Moved {} courses from {from_prefix} to {to_prefix}",
+        new_ids.len()
+    );
+    Ok(())
+}
+
+/// Returns the IDs of all the courses in the library that depend on the course with the given ID.
+fn find_dependents(trane: &Trane, id: Ustr) -> Vec<Ustr> {
+    library::transcription_courses(trane)
+        .into_iter()
+        .filter(|(_, config)| config.transcription_dependencies.contains(&id))
+        .map(|(manifest, _)| manifest.id)
+        .collect()
+}
+
+/// Deletes the course with the given ID. The deletion is refused if the directory of the course
+/// contains other courses. It is also refused if other courses depend on it, unless `force` is
+/// true, in which case the dependency is removed from those courses.
+fn delete_course(root: &Path, changes: FileChanges, id: &str, force: bool) -> Result<()> {
+    // Check that the course exists.
+    let courses_root = library::courses_root(root)?;
+    let directory = library::course_directory(&courses_root, id);
+    if !directory.join(MANIFEST_FILE).exists() {
+        bail!("course does not exist at {}", directory.display());
+    }
+
+    // Refuse to delete the course if its directory contains other courses, since they would be
+    // deleted along with it.
+    let id = library::normalize_course_id(id);
+    let nested = library::nested_manifests(&directory)?;
+    if !nested.is_empty() {
+        println!("The directory of course {id} contains other courses:");
+        for path in &nested {
+            println!("  {}", path.display());
+        }
+        bail!("cannot delete course {id} because its directory contains other courses");
+    }
+
+    // Find all the courses that depend on the course to delete.
+    let trane = library::open_library(root)?;
+    let dependents = find_dependents(&trane, id);
+
+    // Refuse to delete the course if other courses depend on it, unless the deletion is forced.
+    if !dependents.is_empty() {
+        if !force {
+            println!("The following courses depend on course {id}:");
+            for dependent in &dependents {
+                println!("  {dependent}");
+            }
+            bail!("cannot delete course {id} because other courses depend on it");
+        }
+
+        for manifest_path in library::find_manifests(&courses_root)? {
+            let mut manifest = library::read_manifest(&manifest_path)?;
+            if !dependents.contains(&manifest.id) {
+                continue;
+            }
+            if let Some(CourseGenerator::Transcription(config)) = &mut manifest.generator_config {
+                config
+                    .transcription_dependencies
+                    .retain(|dependency| *dependency != id);
+            }
+            changes.write_manifest(&manifest_path, &manifest)?;
+            status!("Removed the dependency on {id} from course {}", manifest.id);
+        }
+    }
+
+    // Delete the course directory.
+    changes.remove_dir_all(&directory)
+}
+
+/// Deletes every transcription course without inlined passages or a passage directory. As with
+/// `delete`, the courses other courses depend on are kept, as are the courses whose directory
+/// contains other courses.
+fn prune_courses(root: &Path, changes: FileChanges) -> Result<()> {
+    let courses_root = library::courses_root(root)?;
+    let manifests = library::read_all_manifests(&courses_root)?;
+    let stubs = manifests
+        .iter()
+        .filter(|(_, manifest)| {
+            library::transcription_config(manifest).is_some_and(|config| {
+                config.inlined_passages.is_empty() && config.passage_directory.is_empty()
+            })
+        })
+        .map(|(path, manifest)| (manifest.id, path.parent().unwrap_or(Path::new("."))))
+        .collect::<Vec<_>>();
+    if stubs.is_empty() {
+        status!("No empty courses found.");
+        return Ok(());
+    }
+
+    let trane = library::open_library(root)?;
+    let mut pruned = 0;
+    for (id, directory) in stubs {
+        let dependents = find_dependents(&trane, id);
+        if !dependents.is_empty() {
+            let dependents = dependents.iter().map(Ustr::as_str).collect::<Vec<_>>();
+            status!(
+                "Kept course {id} because other courses depend on it: {}",
+                dependents.join(", ")
+            );
+            continue;
+        }
+        let contains_courses = manifests
+            .iter()
+            .any(|(path, manifest)| manifest.id != id && path.starts_with(directory));
+        if contains_courses {
+            status!("Kept course {id} because its directory contains other courses");
+            continue;
+        }
+
+        changes.remove_dir_all(directory)?;
+        pruned += 1;
+        status!("Pruned course {id}");
+    }
+    status!("Pruned {pruned} empty courses.");
+    Ok(())
+}
+
+/// Verifies that all transcription courses are valid. If `only` is not empty, the checks of
+/// individual courses are restricted to the given courses, but the checks that involve multiple
+/// courses, like the dependency graph, still cover the whole library. The courses matching the
+/// `exclude` or `ignored` patterns are skipped, except in the checks of the dependency graph.
+fn verify_courses(root: &Path, only: &[Ustr], exclude: &[Ustr], ignored: &[Ustr]) -> Result<()> {
+    // Run the checks on the manifests first, since some issues, like dependency cycles, would also
+    // prevent the library from opening.
+    let courses_root = library::courses_root(root)?;
+    let manifests_with_paths = library::read_all_manifests(&courses_root)?;
+    let manifests = manifests_with_paths
+        .iter()
+        .map(|(_, manifest)| manifest.clone())
+        .collect::<Vec<_>>();
+    let course_ids = manifests
+        .iter()
+        .map(|manifest| manifest.id)
+        .collect::<Vec<_>>();
+    library::warn_unknown_courses(only, &course_ids);
+    library::warn_unknown_courses(exclude, &course_ids);
+    let (excluded_with_paths, selected_with_paths): (Vec<_>, Vec<_>) = manifests_with_paths
+        .into_iter()
+        .filter(|(_, manifest)| library::is_selected(only, manifest.id))
+        .partition(|(_, manifest)| library::is_excluded(exclude, manifest.id));
+    let (ignored_with_paths, selected_with_paths): (Vec<_>, Vec<_>) = selected_with_paths
+        .into_iter()
+        .partition(|(_, manifest)| ignore::is_ignored(ignored, manifest.id));
+    let checked = manifests
+        .iter()
+        .filter(|manifest| {
+            !library::is_excluded(exclude, manifest.id) && !ignore::is_ignored(ignored, manifest.id)
+        })
+        .cloned()
+        .collect::<Vec<_>>();
+    if !excluded_with_paths.is_empty() {
+        status!("Excluded {} courses.", excluded_with_paths.len());
+    }
+    ignore::report_ignored(
+        &ignored_with_paths
+            .iter()
+            .map(|(_, manifest)| manifest.id)
+            .collect::<Vec<_>>(),
+    );
+
+    let mut issues = checks::verify_dependency_graph(&manifests);
+    issues += checks::verify_unique_short_ids(&checked);
+    issues += checks::verify_unique_external_links(&courses_root, &checked)?;
+    issues +=
+        checks::verify_each_course(&courses_root, &selected_with_paths, &CourseCheck::ALL).len();
+    if issues > 0 {
+        bail!(Failure::new(
+            FailureKind::InvalidCourses,
+            format!("found {issues} issues in the courses")
+        ));
+    }
+
+    // Open the trane-transcription library in trane.
+    let _ = library::open_library(root)?;
+    Ok(())
+}
+
+/// Prints the dependency graph of all the courses in the given format, or writes it to `output` if
+/// set. If `reverse` is true, the edges are inverted. If `reachable_from` is set, only the IDs of
+/// the courses reachable from that course are printed, that is, its dependencies or, with
+/// `reverse`, its dependents.
+fn print_graph(
+    root: &Path,
+    format: GraphFormat,
+    output: Option<&Path>,
+    reverse: bool,
+    reachable_from: Option<&str>,
+) -> Result<()> {
+    let manifests = library::read_all_manifests(&library::courses_root(root)?)?;
+    let mut graph = DependencyGraph::from_manifests(manifests.iter().map(|(_, manifest)| manifest));
+    if reverse {
+        graph = graph.reversed();
+    }
+    let rendered = match reachable_from {
+        Some(id) => {
+            let course_id = library::normalize_course_id(id);
+            if !graph.courses().any(|other| other == course_id) {
+                bail!("course {course_id} does not exist");
+            }
+            graph
+                .reachable_from(course_id)
+                .iter()
+                .map(|course_id| format!("{course_id}\n"))
+                .collect()
+        }
+        None => graph.render(format),
+    };
+    match output {
+        Some(output) => fs::write(output, rendered)
+            .with_context(|| format!("failed to write graph to {}", output.display())),
+        None => {
+            print!("{rendered}");
+            Ok(())
+        }
+    }
+}
+
+/// Verifies a single transcription course without opening the whole library.
+fn validate_course(root: &Path, id: &str) -> Result<()> {
+    let courses_root = library::courses_root(root)?;
+    let directory = library::course_directory(&courses_root, id);
+    let manifest_path = directory.join(MANIFEST_FILE);
+    if !manifest_path.exists() {
+        bail!(
+            "course {id} does not resolve to a course at {}",
+            directory.display()
+        );
+    }
+    let manifest = library::read_manifest(&manifest_path)?;
+    if library::transcription_config(&manifest).is_none() {
+        bail!("course {} is not a transcription course", manifest.id);
     }
 
-    if invalid_links == 0 {
-        println!("All courses have valid links.");
+    // Run the checks that only depend on the course itself.
+    let manifests_with_paths = vec![(manifest_path, manifest)];
+    let manifests = [manifests_with_paths[0].1.clone()];
+    let mut issues = checks::verify_unique_short_ids(&manifests);
+    issues +=
+        checks::verify_each_course(&courses_root, &manifests_with_paths, &CourseCheck::ALL).len();
+    if issues > 0 {
+        bail!(Failure::new(
+            FailureKind::InvalidCourses,
+            format!("found {issues} issues in course {}", manifests[0].id)
+        ));
     }
     Ok(())
 }
 
+/// The environment variable used as the root of the repository when `--root` is not given.
+const ROOT_ENV_VAR: &str = "TRANE_TRANSCRIPTION_ROOT";
+
+/// Returns the courses selected by the `--only` and `--since` flags, or an empty list if all the
+/// courses are selected. If both are given, only the changed courses matching `--only` are
+/// selected. Returns `None` if `--since` was given but no selected course changed, in which case
+/// there is nothing to verify.
+fn selected_courses(
+    root: &Path,
+    only: &[String],
+    since: Option<&str>,
+) -> Result<Option<Vec<Ustr>>> {
+    let only = library::course_patterns(only);
+    let Some(since) = since else {
+        return Ok(Some(only));
+    };
+    match git::changed_courses(root, since)? {
+        Some(changed) => {
+            let selected = changed
+                .into_iter()
+                .filter(|course_id| library::is_selected(&only, *course_id))
+                .collect::<Vec<_>>();
+            if selected.is_empty() {
+                status!("No selected courses changed since {since}.");
+                return Ok(None);
+            }
+            Ok(Some(selected))
+        }
+        None => {
+            eprintln!("Warning: not in a git repository, so all the courses will be verified");
+            Ok(Some(only))
+        }
+    }
+}
+
+/// Returns the options used to verify a single link, that is, the defaults in the config file with
+/// the `--timeout` and `--user-agent` flags applied.
+fn single_link_options(
+    config: &Config,
+    timeout: Option<u64>,
+    user_agent: Option<&str>,
+) -> VerifyLinksOptions {
+    let defaults = config.link_options();
+    VerifyLinksOptions {
+        timeout: timeout.map_or(defaults.timeout, Duration::from_secs),
+        user_agent: user_agent.map_or(defaults.user_agent, str::to_string),
+        ..defaults
+    }
+}
+
 #[derive(Debug, Parser)]
 #[clap(name = "transcription-cli")]
-#[clap(author, version, about, long_about = None)]
+#[clap(author, version, about, long_about = None, after_help = exit::EXIT_CODES_HELP)]
 pub(crate) struct TranscriptionCLI {
+    #[clap(
+        long,
+        global = true,
+        env = ROOT_ENV_VAR,
+        help = "The root of the trane-transcription repository. If not given, the value of the \
+        TRANE_TRANSCRIPTION_ROOT environment variable is used, and if that is not set either, the \
+        current directory"
+    )]
+    pub root: Option<PathBuf>,
+
+    #[clap(
+        long,
+        global = true,
+        help = "The config file with the defaults of the flags. Defaults to \
+        trane-transcription.toml in the root of the repository, if it exists"
+    )]
+    pub config: Option<PathBuf>,
+
+    #[clap(
+        long,
+        global = true,
+        help = "Print the changes to the courses instead of writing them to disk"
+    )]
+    pub dry_run: bool,
+
+    #[clap(
+        long,
+        global = true,
+        help = "Keep a .bak copy of the previous contents of every file that is overwritten"
+    )]
+    pub backup: bool,
+
+    #[clap(
+        short,
+        long,
+        global = true,
+        action = ArgAction::Count,
+        help = "Print debug information about the requests and the cache. Repeat for more detail"
+    )]
+    pub verbose: u8,
+
+    #[clap(
+        short,
+        long,
+        global = true,
+        conflicts_with = "verbose",
+        help = "Do not print informational messages or progress, only errors and the requested \
+        output"
+    )]
+    pub quiet: bool,
+
+    #[clap(
+        long,
+        global = true,
+        value_enum,
+        default_value_t,
+        help = "When to color the output. auto colors it only if stdout is a terminal"
+    )]
+    pub color: ColorChoice,
+
+    #[clap(
+        long,
+        global = true,
+        help = "Treat warnings as errors, exiting with an error if any warning is found"
+    )]
+    pub strict: bool,
+
     #[clap(subcommand)]
     pub commands: Subcommands,
 }
@@ -169,40 +922,1237 @@ pub(crate) enum Subcommands {
     #[clap(about = "Create a new transcription course")]
     New {
         #[clap(
+            required_unless_present = "from_file",
             help = "The id of the course to create with or without the trane::transcription:: \
             prefix"
         )]
-        id: String,
+        id: Option<String>,
+
+        #[clap(
+            long,
+            conflicts_with_all = ["id", "interactive"],
+            help = "Create a course for each id in this file, one per line. Blank lines and lines \
+            starting with # are ignored"
+        )]
+        from_file: Option<PathBuf>,
+
+        #[clap(
+            long = "dependency",
+            help = "The id of a dependency of the course with or without the \
+            trane::transcription:: prefix. Can be repeated"
+        )]
+        dependencies: Vec<String>,
+
+        #[clap(
+            long,
+            conflicts_with = "from_file",
+            help = "The human-readable name of the course"
+        )]
+        name: Option<String>,
+
+        #[clap(
+            long,
+            conflicts_with = "from_file",
+            help = "The description of the course"
+        )]
+        description: Option<String>,
+
+        #[clap(
+            long,
+            value_enum,
+            default_value_t,
+            help = "The template used to create the course"
+        )]
+        template: CourseTemplate,
+
+        #[clap(
+            long,
+            help = "Ask for the details of the course instead of using the defaults"
+        )]
+        interactive: bool,
+
+        #[clap(
+            long,
+            value_enum,
+            default_value_t,
+            conflicts_with = "from_file",
+            help = "The format used to print the id and path of the created course"
+        )]
+        format: ReportFormat,
     },
 
-    #[clap(about = "Verify that all transcription courses are valid")]
-    VerifyCourses,
+    #[clap(about = "Add a transcription dependency to a course")]
+    AddDependency {
+        #[clap(help = "The id of the course with or without the trane::transcription:: prefix")]
+        course: String,
 
-    #[clap(about = "Verify that all links in the transcription courses are valid")]
-    VerifyLinks,
-}
+        #[clap(
+            help = "The id of the new dependency with or without the trane::transcription:: prefix"
+        )]
+        dependency: String,
+    },
 
-impl Subcommands {
-    /// Executes the subcommand.
-    pub fn execute(&self) -> Result<()> {
-        match self {
-            Subcommands::New { id } => create_course(id)?,
+    #[clap(about = "Remove a transcription dependency from a course")]
+    RemoveDependency {
+        #[clap(help = "The id of the course with or without the trane::transcription:: prefix")]
+        course: String,
 
-            Subcommands::VerifyCourses => match verify_courses() {
-                Ok(_) => println!("All courses are valid."),
-                Err(e) => eprintln!("Error validating courses: {e}"),
-            },
+        #[clap(
+            help = "The id of the dependency to remove with or without the trane::transcription:: \
+            prefix"
+        )]
+        dependency: String,
+    },
 
-            Subcommands::VerifyLinks => verify_links()?,
-        }
-        Ok(())
-    }
-}
+    #[clap(about = "Regenerate the index of all the courses at the root of the repository")]
+    Reindex {
+        #[clap(
+            long,
+            help = "Exit with an error if the index is out of date instead of regenerating it"
+        )]
+        check: bool,
+    },
 
-fn main() -> Result<()> {
-    let args = TranscriptionCLI::parse();
-    args.commands.execute()?;
-    Ok(())
+    #[clap(about = "Remove a passage from a transcription course")]
+    RemovePassage {
+        #[clap(help = "The id of the course with or without the trane::transcription:: prefix")]
+        course: String,
+
+        #[clap(help = "The short id of the passage to remove")]
+        short_id: String,
+    },
+
+    #[clap(about = "Replace a link in every passage that uses it, for example when a video moves")]
+    ReplaceLink {
+        #[clap(help = "The link to replace. YouTube links match any link to the same video")]
+        old: String,
+
+        #[clap(help = "The link replacing the old one")]
+        new: String,
+
+        #[clap(long, help = "Replace the link without verifying the new link")]
+        skip_verify: bool,
+
+        #[clap(
+            long,
+            help = "The number of seconds to wait to connect to a host and for each read of the \
+            response. Defaults to the value in the config file or 15"
+        )]
+        timeout: Option<u64>,
+
+        #[clap(
+            long,
+            help = "The User-Agent header sent with the requests to verify the link. Defaults to \
+            the name and version of this tool"
+        )]
+        user_agent: Option<String>,
+    },
+
+    #[clap(about = "Sort the inlined passages of every transcription course by short id")]
+    SortPassages {
+        #[clap(
+            long,
+            help = "Report the courses whose passages are not sorted and exit with an error \
+            instead of sorting them"
+        )]
+        check: bool,
+    },
+
+    #[clap(
+        about = "Rewrite every YouTube link to the canonical https://www.youtube.com/watch?v=<id> \
+        form, keeping its timestamps"
+    )]
+    NormalizeLinks {
+        #[clap(
+            long,
+            help = "Report the links that need to be normalized and exit with an error instead of \
+            rewriting them"
+        )]
+        check: bool,
+    },
+
+    #[clap(about = "Rename a transcription course and update the courses depending on it")]
+    Rename {
+        #[clap(
+            help = "The current id of the course with or without the trane::transcription:: prefix"
+        )]
+        old_id: String,
+
+        #[clap(
+            help = "The new id of the course with or without the trane::transcription:: prefix"
+        )]
+        new_id: String,
+    },
+
+    #[clap(about = "Move all the courses under an id prefix to a new prefix")]
+    Move {
+        #[clap(
+            help = "The prefix of the ids of the courses to move with or without the \
+            trane::transcription:: prefix"
+        )]
+        from_prefix: String,
+
+        #[clap(
+            help = "The prefix replacing the old one with or without the trane::transcription:: \
+            prefix"
+        )]
+        to_prefix: String,
+    },
+
+    #[clap(about = "Delete a transcription course")]
+    Delete {
+        #[clap(
+            help = "The id of the course to delete with or without the trane::transcription:: \
+            prefix"
+        )]
+        id: String,
+
+        #[clap(
+            long,
+            help = "Delete the course even if other courses depend on it and remove it from their \
+            dependencies"
+        )]
+        force: bool,
+    },
+
+    #[clap(
+        about = "Delete the transcription courses without passages that no other course depends on"
+    )]
+    Prune,
+
+    #[clap(about = "List all transcription courses")]
+    List {
+        #[clap(
+            long,
+            help = "Only include the course with this id, with or without the \
+            trane::transcription:: prefix. The id can be a glob pattern, in which * matches within \
+            a segment and ** matches any number of segments. Can be repeated"
+        )]
+        only: Vec<String>,
+
+        #[clap(long, value_enum, default_value_t, help = "The format of the output")]
+        format: OutputFormat,
+    },
+
+    #[clap(about = "Print the courses as a tree grouped by the segments of their ids")]
+    Tree {
+        #[clap(long, help = "The maximum number of levels of the tree to print")]
+        depth: Option<usize>,
+    },
+
+    #[clap(about = "Add a passage covering a whole track to a transcription course")]
+    AddPassage {
+        #[clap(
+            long,
+            help = "The id of the course with or without the trane::transcription:: prefix"
+        )]
+        course: String,
+
+        #[clap(long, help = "The short id of the new passage")]
+        short_id: String,
+
+        #[clap(long, help = "The name of the track. Defaults to the short id")]
+        track_name: Option<String>,
+
+        #[clap(long, help = "The name of the artist who performs the track")]
+        artist_name: Option<String>,
+
+        #[clap(long, help = "A YouTube link to the track")]
+        youtube: Option<String>,
+
+        #[clap(
+            long = "link",
+            help = "A link to the track on any platform. Can be repeated to link to the same track \
+            on several platforms, and each link is verified independently"
+        )]
+        links: Vec<String>,
+
+        #[clap(
+            long,
+            requires = "youtube",
+            help = "The second of the video at which the passage starts"
+        )]
+        start: Option<u32>,
+
+        #[clap(
+            long,
+            requires = "youtube",
+            help = "The second of the video at which the passage ends"
+        )]
+        end: Option<u32>,
+
+        #[clap(long, help = "Add the passage without verifying its link")]
+        skip_verify: bool,
+
+        #[clap(
+            long,
+            help = "The number of seconds to wait to connect to a host and for each read of the \
+            response. Defaults to the value in the config file or 15"
+        )]
+        timeout: Option<u64>,
+
+        #[clap(
+            long,
+            help = "The User-Agent header sent with the requests to verify the link. Defaults to \
+            the name and version of this tool"
+        )]
+        user_agent: Option<String>,
+    },
+
+    #[clap(about = "Compare a course with the manifest that would be generated for it today")]
+    Check {
+        #[clap(
+            help = "The id of the course to check with or without the trane::transcription:: \
+            prefix"
+        )]
+        id: String,
+    },
+
+    #[clap(
+        hide = true,
+        about = "Measure how long it takes to load the library and count its exercises"
+    )]
+    Bench {
+        #[clap(
+            long,
+            default_value_t = 5,
+            help = "The number of times to load the library"
+        )]
+        iterations: usize,
+
+        #[clap(long, value_enum, default_value_t, help = "The format of the output")]
+        format: ReportFormat,
+    },
+
+    #[clap(about = "Count the exercises generated by the library")]
+    CountExercises {
+        #[clap(
+            long,
+            help = "Also print the number of exercises generated by each course"
+        )]
+        per_course: bool,
+
+        #[clap(
+            long,
+            help = "Only include the course with this id, with or without the \
+            trane::transcription:: prefix. The id can be a glob pattern, in which * matches within \
+            a segment and ** matches any number of segments. Can be repeated"
+        )]
+        only: Vec<String>,
+
+        #[clap(long, value_enum, default_value_t, help = "The format of the output")]
+        format: ReportFormat,
+    },
+
+    #[clap(about = "Classify the courses as empty, partial, or complete by their passages")]
+    Completeness {
+        #[clap(
+            long,
+            default_value_t = 5,
+            help = "The minimum number of passages for a course to be complete"
+        )]
+        threshold: usize,
+
+        #[clap(
+            long,
+            help = "Only include the course with this id, with or without the \
+            trane::transcription:: prefix. The id can be a glob pattern, in which * matches within \
+            a segment and ** matches any number of segments. Can be repeated"
+        )]
+        only: Vec<String>,
+
+        #[clap(
+            long,
+            help = "Skip the course with this id, with or without the trane::transcription:: \
+            prefix, even if it is selected by --only. Accepts the same glob patterns as --only. \
+            Can be repeated"
+        )]
+        exclude: Vec<String>,
+
+        #[clap(long, value_enum, default_value_t, help = "The format of the output")]
+        format: ReportFormat,
+
+        #[clap(
+            long,
+            help = "Write the report to this file instead of stdout, creating its parent \
+            directories if needed"
+        )]
+        output_file: Option<PathBuf>,
+    },
+
+    #[clap(about = "Print a shell completion script to be sourced by the given shell")]
+    Completions {
+        #[clap(value_enum, help = "The shell for which to generate the completions")]
+        shell: Shell,
+    },
+
+    #[clap(about = "Find pairs of courses whose ids are so similar they might be duplicates")]
+    Dedupe {
+        #[clap(
+            long,
+            default_value_t = 3,
+            help = "The maximum number of edits between the last segments of two course ids for \
+            them to be reported"
+        )]
+        threshold: usize,
+
+        #[clap(long, help = "Exit with an error if any possible duplicates are found")]
+        fail: bool,
+    },
+
+    #[clap(about = "List the courses added, removed, or changed relative to another checkout")]
+    Diff {
+        #[clap(help = "The root of the other checkout of the repository")]
+        other_root: PathBuf,
+
+        #[clap(long, value_enum, default_value_t, help = "The format of the output")]
+        format: ReportFormat,
+    },
+
+    #[clap(about = "Run all the checks on the courses and their links")]
+    Doctor {
+        #[clap(long, help = "Skip checking that the library can be opened")]
+        skip_library: bool,
+
+        #[clap(long, help = "Skip checking the dependency graph")]
+        skip_dependencies: bool,
+
+        #[clap(
+            long,
+            help = "Skip checking that the courses are in the directories matching their ids"
+        )]
+        skip_locations: bool,
+
+        #[clap(
+            long,
+            help = "Skip checking that the course ids contain only the allowed characters"
+        )]
+        skip_ids: bool,
+
+        #[clap(long, help = "Skip checking that every course lists its authors")]
+        skip_authors: bool,
+
+        #[clap(long, help = "Skip checking for invalid and duplicate short IDs")]
+        skip_short_ids: bool,
+
+        #[clap(long, help = "Skip checking the passage directories")]
+        skip_passage_directories: bool,
+
+        #[clap(long, help = "Skip checking for passages that link to the same video")]
+        skip_duplicate_links: bool,
+
+        #[clap(long, help = "Skip verifying the links")]
+        skip_links: bool,
+    },
+
+    #[clap(about = "Export the manifests of all transcription courses to a single JSON file")]
+    Export {
+        #[clap(help = "The path to the file to write")]
+        output: PathBuf,
+    },
+
+    #[clap(
+        about = "Repair missing authors and course series, non-canonical formatting, and courses \
+        in the wrong directory"
+    )]
+    Fix {
+        #[clap(
+            long,
+            help = "Also sort the inlined passages of each course by short id"
+        )]
+        sort_passages: bool,
+    },
+
+    #[clap(about = "Print the graph of dependencies among the transcription courses")]
+    Graph {
+        #[clap(long, value_enum, default_value_t, help = "The format of the graph")]
+        format: GraphFormat,
+
+        #[clap(long, help = "Write the graph to this file instead of printing it")]
+        output: Option<PathBuf>,
+
+        #[clap(
+            long,
+            help = "Invert the edges of the graph, so each course points to its dependencies \
+            instead of its dependents"
+        )]
+        reverse: bool,
+
+        #[clap(
+            long,
+            help = "Print the ids of all the direct and indirect dependencies of the course with \
+            this id instead of the graph, or of its dependents with --reverse"
+        )]
+        reachable_from: Option<String>,
+    },
+
+    #[clap(about = "Import the courses from a JSON file created by the export subcommand")]
+    Import {
+        #[clap(help = "The path to the file to read")]
+        input: PathBuf,
+
+        #[clap(long, help = "Overwrite the courses that already exist")]
+        overwrite: bool,
+    },
+
+    #[clap(about = "Check that the course manifests follow the conventions of the repository")]
+    Lint {
+        #[clap(
+            long = "allow",
+            help = "The name of a lint rule to skip. Can be repeated"
+        )]
+        allowed: Vec<String>,
+
+        #[clap(
+            long,
+            help = "Also check the courses listed in the .trane-transcription-ignore file"
+        )]
+        no_ignore: bool,
+    },
+
+    #[clap(about = "List the inlined passages of a transcription course")]
+    Passages {
+        #[clap(help = "The id of the course with or without the trane::transcription:: prefix")]
+        id: String,
+
+        #[clap(long, value_enum, default_value_t, help = "The format of the output")]
+        format: ReportFormat,
+    },
+
+    #[clap(about = "Search for courses whose id, authors, or metadata contain a query")]
+    Search {
+        #[clap(help = "The text to search for, ignoring case")]
+        query: String,
+
+        #[clap(long, help = "Only search the values of the given metadata key")]
+        metadata_key: Option<String>,
+
+        #[clap(long, value_enum, default_value_t, help = "The format of the output")]
+        format: ReportFormat,
+    },
+
+    #[clap(about = "Find files in the passage directories that are not read as passages")]
+    Orphans {
+        #[clap(long, help = "Exit with an error if any orphaned files are found")]
+        fail: bool,
+    },
+
+    #[clap(about = "Print statistics about the transcription courses")]
+    Stats {
+        #[clap(
+            long,
+            help = "Only include the course with this id, with or without the \
+            trane::transcription:: prefix. The id can be a glob pattern, in which * matches within \
+            a segment and ** matches any number of segments. Can be repeated"
+        )]
+        only: Vec<String>,
+
+        #[clap(
+            long,
+            help = "Skip the course with this id, with or without the trane::transcription:: \
+            prefix, even if it is selected by --only. Accepts the same glob patterns as --only. \
+            Can be repeated"
+        )]
+        exclude: Vec<String>,
+
+        #[clap(long, value_enum, default_value_t, help = "The format of the output")]
+        format: ReportFormat,
+
+        #[clap(
+            long,
+            help = "Write the report to this file instead of stdout, creating its parent \
+            directories if needed"
+        )]
+        output_file: Option<PathBuf>,
+    },
+
+    #[clap(
+        about = "Print a JSON summary of the library with the number of courses, passages, links, \
+        broken links, and lint violations, the graph statistics, and the completeness of the \
+        courses"
+    )]
+    Summary {
+        #[clap(
+            long,
+            default_value_t = 5,
+            help = "The minimum number of passages for a course to be complete"
+        )]
+        threshold: usize,
+
+        #[clap(
+            long,
+            help = "Also count the lint violations of the courses listed in the \
+            .trane-transcription-ignore file"
+        )]
+        no_ignore: bool,
+    },
+
+    #[clap(about = "Rewrite all course manifests using the canonical formatting")]
+    Touch {
+        #[clap(
+            long,
+            help = "Report the manifests that need reformatting and exit with an error instead of \
+            rewriting them"
+        )]
+        check: bool,
+    },
+
+    #[clap(about = "Verify a single transcription course without loading the whole library")]
+    Validate {
+        #[clap(
+            required_unless_present = "schema",
+            help = "The id of the course to validate with or without the trane::transcription:: \
+            prefix. Can be omitted with --schema to validate all the courses"
+        )]
+        id: Option<String>,
+
+        #[clap(
+            long,
+            help = "Only validate the manifest against the JSON Schema printed by the schema \
+            subcommand, reporting the path to every offending value"
+        )]
+        schema: bool,
+    },
+
+    #[clap(about = "Print the JSON Schema of the course manifests")]
+    Schema,
+
+    #[clap(about = "Verify that all transcription courses are valid")]
+    VerifyCourses {
+        #[clap(
+            long,
+            help = "Only verify the course with this id, with or without the \
+            trane::transcription:: prefix. The id can be a glob pattern, in which * matches within \
+            a segment and ** matches any number of segments. Can be repeated"
+        )]
+        only: Vec<String>,
+
+        #[clap(
+            long,
+            help = "Skip the course with this id, with or without the trane::transcription:: \
+            prefix, even if it is selected by --only. Accepts the same glob patterns as --only. \
+            Can be repeated"
+        )]
+        exclude: Vec<String>,
+
+        #[clap(
+            long,
+            help = "Only verify the courses whose manifests changed since this git ref and the \
+            courses depending on them, counting untracked manifests as changed. Combined with \
+            --only, only the changed courses matching it are verified"
+        )]
+        since: Option<String>,
+
+        #[clap(
+            long,
+            help = "Also check the courses listed in the .trane-transcription-ignore file"
+        )]
+        no_ignore: bool,
+    },
+
+    #[clap(about = "Verify that all links in the transcription courses are valid")]
+    VerifyLinks {
+        #[clap(
+            long,
+            help = "The number of links to verify in parallel. Defaults to the value in the config \
+            file or the number of CPUs. Use 1 to verify the links serially"
+        )]
+        jobs: Option<usize>,
+
+        #[clap(long, help = "Verify all links, even those verified recently")]
+        no_cache: bool,
+
+        #[clap(
+            long,
+            help = "The number of days for which a successful verification is cached. Defaults to \
+            the value in the config file or 7"
+        )]
+        cache_ttl: Option<u64>,
+
+        #[clap(
+            long,
+            default_value_t = 2,
+            help = "The number of times to retry a link whose verification failed for a reason \
+            other than the video not existing"
+        )]
+        max_retries: u32,
+
+        #[clap(long, help = "Exit with an error if any invalid links are found")]
+        fail_on_invalid: bool,
+
+        #[clap(
+            long,
+            help = "The number of seconds to wait to connect to a host and for each read of the \
+            response. Defaults to the value in the config file or 15"
+        )]
+        timeout: Option<u64>,
+
+        #[clap(long, value_enum, default_value_t, help = "The format of the output")]
+        format: ReportFormat,
+
+        #[clap(
+            long,
+            conflicts_with = "format",
+            help = "Print the result of each link as a JSON object on its own line as soon as it \
+            is verified. The summary is printed to stderr"
+        )]
+        json_lines: bool,
+
+        #[clap(
+            long,
+            conflicts_with = "json_lines",
+            help = "Write the report to this file instead of stdout, creating its parent \
+            directories if needed. Only the summary is printed"
+        )]
+        output_file: Option<PathBuf>,
+
+        #[clap(
+            long,
+            help = "The User-Agent header sent with the requests to verify the links. Defaults to \
+            the name and version of this tool"
+        )]
+        user_agent: Option<String>,
+
+        #[clap(
+            long = "platform",
+            value_enum,
+            help = "Only verify the links on this platform. Can be repeated"
+        )]
+        platforms: Vec<Platform>,
+
+        #[clap(
+            long,
+            help = "Only verify the links of the course with this id, with or without the \
+            trane::transcription:: prefix. The id can be a glob pattern, in which * matches within \
+            a segment and ** matches any number of segments. Can be repeated"
+        )]
+        only: Vec<String>,
+
+        #[clap(
+            long,
+            help = "Skip the course with this id, with or without the trane::transcription:: \
+            prefix, even if it is selected by --only. Accepts the same glob patterns as --only. \
+            Can be repeated"
+        )]
+        exclude: Vec<String>,
+
+        #[clap(
+            long,
+            help = "Only verify the links of the courses whose manifests changed since this git \
+            ref and the courses depending on them, counting untracked manifests as changed. \
+            Combined with --only, only the changed courses matching it are verified"
+        )]
+        since: Option<String>,
+
+        #[clap(
+            long,
+            help = "Also verify the links of the courses listed in the .trane-transcription-ignore \
+            file"
+        )]
+        no_ignore: bool,
+
+        #[clap(
+            long,
+            help = "Also report every valid link along with its course and short id. In the JSON \
+            output, the results array includes every link regardless of its status"
+        )]
+        report_valid: bool,
+
+        #[clap(
+            long,
+            help = "Verify YouTube links with a HEAD request to the page of the video instead of \
+            fetching its oembed data. This is faster, but restricted videos are not detected and \
+            are reported as valid"
+        )]
+        head_only: bool,
+
+        #[clap(
+            long,
+            conflicts_with = "head_only",
+            help = "Also download the page of each YouTube video to detect age-restricted videos. \
+            This doubles the number of requests per YouTube link and downloads the whole page, so \
+            it is much slower. The results are cached separately from those of the other checks. \
+            Can also be enabled with the check_age_restriction key of the config file"
+        )]
+        check_age_restriction: bool,
+
+        #[clap(
+            long,
+            value_parser = clap::value_parser!(u64).range(1..),
+            help = "Stop verifying links once this many invalid links are found"
+        )]
+        max_failures: Option<u64>,
+
+        #[clap(
+            long,
+            overrides_with = "no_progress",
+            help = "Print the number of links verified so far. Enabled by default when stderr is \
+            a terminal"
+        )]
+        progress: bool,
+
+        #[clap(
+            long,
+            overrides_with = "progress",
+            help = "Do not print the number of links verified so far"
+        )]
+        no_progress: bool,
+    },
+
+    #[clap(about = "List every passage linking to a video, for example when it is taken down")]
+    WhereUsed {
+        #[clap(help = "The link to look for. YouTube links match any link to the same video")]
+        link: String,
+
+        #[clap(long, value_enum, default_value_t, help = "The format of the output")]
+        format: ReportFormat,
+    },
+
+    #[clap(
+        about = "Verify a single link without loading the library and print its status and the \
+        title of the recording"
+    )]
+    ValidateLink {
+        #[clap(help = "The link to verify. The platform is detected from the URL")]
+        url: String,
+
+        #[clap(
+            long,
+            help = "The number of seconds to wait to connect to a host and for each read of the \
+            response. Defaults to the value in the config file or 15"
+        )]
+        timeout: Option<u64>,
+
+        #[clap(
+            long,
+            help = "The User-Agent header sent with the requests to verify the link. Defaults to \
+            the name and version of this tool"
+        )]
+        user_agent: Option<String>,
+    },
+}
+
+impl Subcommands {
+    /// Executes the subcommand against the repository at the given root, making the changes to the
+    /// courses through `changes` and using the defaults in `config` for the flags not given.
+    pub fn execute(&self, root: &Path, changes: FileChanges, config: &Config) -> Result<()> {
+        match self {
+            Subcommands::New {
+                id,
+                from_file,
+                dependencies,
+                name,
+                description,
+                template,
+                interactive,
+                format,
+            } => {
+                let config = template.config();
+                let options = NewCourseOptions {
+                    name: name.clone(),
+                    description: description.clone(),
+                    dependencies: dependencies
+                        .iter()
+                        .map(|id| library::normalize_course_id(id))
+                        .collect(),
+                    skip_singing_lessons: config.skip_singing_lessons,
+                    skip_advanced_lessons: config.skip_advanced_lessons,
+                    ..Default::default()
+                };
+                match (id, from_file) {
+                    (_, Some(path)) => {
+                        create_courses_from_file(root, changes, path, *template, &options)?
+                    }
+                    (Some(id), None) => {
+                        create_course(root, changes, id, *template, options, *interactive, *format)?
+                            .print(*format, changes.dry_run)?
+                    }
+                    (None, None) => bail!(Failure::new(
+                        FailureKind::Usage,
+                        "either a course id or --from-file must be given"
+                    )),
+                }
+            }
+
+            Subcommands::AddDependency { course, dependency } => {
+                dependencies::add_dependency(root, changes, course, dependency)?
+            }
+
+            Subcommands::RemoveDependency { course, dependency } => {
+                dependencies::remove_dependency(root, changes, course, dependency)?
+            }
+
+            Subcommands::Reindex { check } => index::reindex(root, changes, *check)?,
+
+            Subcommands::RemovePassage { course, short_id } => {
+                passages::remove_passage(root, changes, course, short_id)?
+            }
+
+            Subcommands::ReplaceLink {
+                old,
+                new,
+                skip_verify,
+                timeout,
+                user_agent,
+            } => {
+                let options = single_link_options(config, *timeout, user_agent.as_deref());
+                passages::replace_link(root, changes, old, new, *skip_verify, &options)?
+            }
+
+            Subcommands::SortPassages { check } => passages::sort_passages(root, changes, *check)?,
+
+            Subcommands::NormalizeLinks { check } => {
+                passages::normalize_links(root, changes, *check)?
+            }
+
+            Subcommands::Rename { old_id, new_id } => rename_course(root, changes, old_id, new_id)?,
+
+            Subcommands::Move {
+                from_prefix,
+                to_prefix,
+            } => move_courses(root, changes, from_prefix, to_prefix)?,
+
+            Subcommands::Delete { id, force } => delete_course(root, changes, id, *force)?,
+
+            Subcommands::Prune => prune_courses(root, changes)?,
+
+            Subcommands::List { only, format } => {
+                reports::list_courses(root, &library::course_patterns(only), *format)?
+            }
+
+            Subcommands::Tree { depth } => tree::print_tree(root, *depth)?,
+
+            Subcommands::AddPassage {
+                course,
+                short_id,
+                track_name,
+                artist_name,
+                youtube,
+                links: additional_links,
+                start,
+                end,
+                skip_verify,
+                timeout,
+                user_agent,
+            } => {
+                // Only rewrite the link if the passage covers part of the video.
+                let youtube = match (youtube, start.is_some() || end.is_some()) {
+                    (Some(link), true) => Some(links::youtube_link_with_range(link, *start, *end)?),
+                    (link, _) => link.clone(),
+                };
+                // The first link is the external link of the track, and the rest are additional.
+                let mut links = youtube.into_iter().chain(additional_links.iter().cloned());
+                let passage = NewPassage {
+                    short_id: short_id.clone(),
+                    track_name: track_name.clone(),
+                    artist_name: artist_name.clone(),
+                    external_link: links.next().map(TranscriptionLink::YouTube),
+                    additional_links: links.collect(),
+                };
+                let options = single_link_options(config, *timeout, user_agent.as_deref());
+                passages::add_passage(root, changes, course, passage, *skip_verify, &options)?;
+            }
+
+            Subcommands::Check { id } => check_course(root, id)?,
+
+            Subcommands::Bench { iterations, format } => {
+                bench::bench_library(root, *iterations, *format)?
+            }
+
+            Subcommands::CountExercises {
+                per_course,
+                only,
+                format,
+            } => reports::count_exercises(
+                root,
+                *per_course,
+                &library::course_patterns(only),
+                *format,
+            )?,
+
+            Subcommands::Completeness {
+                threshold,
+                only,
+                exclude,
+                format,
+                output_file,
+            } => reports::print_completeness(
+                root,
+                *threshold,
+                &library::course_patterns(only),
+                &library::course_patterns(exclude),
+                *format,
+                output_file.as_deref(),
+            )?,
+
+            Subcommands::Completions { shell } => {
+                // Complete the name of the installed binary rather than the name of the command.
+                let mut command = TranscriptionCLI::command();
+                let name = env!("CARGO_BIN_NAME");
+                clap_complete::generate(*shell, &mut command, name, &mut io::stdout());
+            }
+
+            Subcommands::Dedupe { threshold, fail } => {
+                dedupe::dedupe_courses(root, *threshold, *fail)?
+            }
+
+            Subcommands::Diff { other_root, format } => {
+                diff::diff_courses(root, other_root, *format)?
+            }
+
+            Subcommands::Doctor {
+                skip_library,
+                skip_dependencies,
+                skip_locations,
+                skip_ids,
+                skip_authors,
+                skip_short_ids,
+                skip_passage_directories,
+                skip_duplicate_links,
+                skip_links,
+            } => {
+                let checks = DoctorChecks {
+                    library: !skip_library,
+                    dependencies: !skip_dependencies,
+                    locations: !skip_locations,
+                    ids: !skip_ids,
+                    authors: !skip_authors,
+                    short_ids: !skip_short_ids,
+                    passage_directories: !skip_passage_directories,
+                    duplicate_links: !skip_duplicate_links,
+                    links: !skip_links,
+                };
+                doctor::run_doctor(root, &checks, &config.link_options())?;
+            }
+
+            Subcommands::Export { output } => archive::export_library(root, output)?,
+
+            Subcommands::Fix { sort_passages } => fix::fix_courses(root, changes, *sort_passages)?,
+
+            Subcommands::Graph {
+                format,
+                output,
+                reverse,
+                reachable_from,
+            } => print_graph(
+                root,
+                *format,
+                output.as_deref(),
+                *reverse,
+                reachable_from.as_deref(),
+            )?,
+
+            Subcommands::Import { input, overwrite } => {
+                archive::import_library(root, changes, input, *overwrite)?
+            }
+
+            Subcommands::Lint { allowed, no_ignore } => lint::lint(
+                root,
+                allowed,
+                &config.known_course_series(),
+                &ignore::ignored_courses(root, *no_ignore)?,
+            )?,
+
+            Subcommands::Passages { id, format } => passages::list_passages(root, id, *format)?,
+
+            Subcommands::Search {
+                query,
+                metadata_key,
+                format,
+            } => search::search_courses(root, query, metadata_key.as_deref(), *format)?,
+
+            Subcommands::Orphans { fail } => orphans::report_orphaned_files(root, *fail)?,
+
+            Subcommands::Stats {
+                only,
+                exclude,
+                format,
+                output_file,
+            } => reports::print_stats(
+                root,
+                &library::course_patterns(only),
+                &library::course_patterns(exclude),
+                *format,
+                output_file.as_deref(),
+            )?,
+
+            Subcommands::Summary {
+                threshold,
+                no_ignore,
+            } => summary::print_summary(
+                root,
+                *threshold,
+                &config.known_course_series(),
+                &ignore::ignored_courses(root, *no_ignore)?,
+            )?,
+
+            Subcommands::Touch { check } => touch::touch_courses(root, changes, *check)?,
+
+            Subcommands::Validate { id, schema: true } => {
+                schema::validate_against_schema(root, id.as_deref())?
+            }
+
+            Subcommands::Validate { id, schema: false } => {
+                // The id is required unless --schema is given.
+                let id = id.as_deref().unwrap_or_default();
+                validate_course(root, id).context("error validating course")?;
+                status!("Course {} is valid.", library::normalize_course_id(id));
+            }
+
+            Subcommands::Schema => schema::print_schema(),
+
+            Subcommands::VerifyCourses {
+                only,
+                exclude,
+                since,
+                no_ignore,
+            } => {
+                let Some(only) = selected_courses(root, only, since.as_deref())? else {
+                    return Ok(());
+                };
+                verify_courses(
+                    root,
+                    &only,
+                    &library::course_patterns(exclude),
+                    &ignore::ignored_courses(root, *no_ignore)?,
+                )
+                .context("error validating courses")?;
+                status!("All courses are valid.");
+            }
+
+            Subcommands::VerifyLinks {
+                jobs,
+                no_cache,
+                cache_ttl,
+                max_retries,
+                fail_on_invalid,
+                timeout,
+                format,
+                json_lines,
+                output_file,
+                user_agent,
+                platforms,
+                only,
+                exclude,
+                since,
+                no_ignore,
+                report_valid,
+                head_only,
+                check_age_restriction,
+                max_failures,
+                progress,
+                no_progress,
+            } => {
+                let Some(only) = selected_courses(root, only, since.as_deref())? else {
+                    return Ok(());
+                };
+                let defaults = config.link_options();
+                let options = VerifyLinksOptions {
+                    jobs: jobs.unwrap_or(defaults.jobs),
+                    use_cache: !no_cache,
+                    cache_ttl: cache_ttl.map_or(defaults.cache_ttl, |days| {
+                        Duration::from_secs(days * 24 * 60 * 60)
+                    }),
+                    max_retries: *max_retries,
+                    fail_on_invalid: *fail_on_invalid,
+                    json_lines: *json_lines,
+                    timeout: timeout.map_or(defaults.timeout, Duration::from_secs),
+                    only,
+                    exclude: library::course_patterns(exclude),
+                    ignored: ignore::ignored_courses(root, *no_ignore)?,
+                    platforms: platforms.clone(),
+                    user_agent: user_agent.clone().unwrap_or(defaults.user_agent),
+                    progress: match (progress, no_progress) {
+                        _ if output::is_quiet() => false,
+                        (true, _) => true,
+                        (_, true) => false,
+                        _ => io::stderr().is_terminal(),
+                    },
+                    report_valid: *report_valid,
+                    head_only: *head_only,
+                    check_age_restriction: *check_age_restriction
+                        || (!*head_only && defaults.check_age_restriction),
+                    max_failures: max_failures
+                        .map(|max| usize::try_from(max).unwrap_or(usize::MAX)),
+                };
+                links::verify_links(root, &options, *format, output_file.as_deref())?;
+            }
+
+            Subcommands::WhereUsed { link, format } => passages::where_used(root, link, *format)?,
+
+            Subcommands::ValidateLink {
+                url,
+                timeout,
+                user_agent,
+            } => {
+                let options = single_link_options(config, *timeout, user_agent.as_deref());
+                links::validate_link(url, &options)?
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Initializes the logger with the level selected by the number of times `--verbose` was given.
+/// Only warnings are printed by default, and the `RUST_LOG` environment variable takes precedence.
+fn init_logger(verbose: u8) {
+    let (level, dependency_level) = match verbose {
+        0 => (LevelFilter::Warn, LevelFilter::Warn),
+        1 => (LevelFilter::Debug, LevelFilter::Warn),
+        2 => (LevelFilter::Trace, LevelFilter::Info),
+        _ => (LevelFilter::Trace, LevelFilter::Trace),
+    };
+    env_logger::Builder::new()
+        .filter_level(dependency_level)
+        .filter_module(env!("CARGO_CRATE_NAME"), level)
+        .parse_default_env()
+        .init();
+}
+
+/// Runs the command given by the arguments.
+fn run(args: TranscriptionCLI) -> Result<()> {
+    init_logger(args.verbose);
+    output::set_quiet(args.quiet);
+    output::set_color(args.color);
+    exit::set_strict(args.strict);
+    let current_dir = std::env::current_dir()?;
+
+    // The root given by the flag or the environment variable takes precedence over the one in the
+    // config file, which in turn takes precedence over the current directory.
+    let root = args.root.as_ref().map(|root| current_dir.join(root));
+    let config = match &args.config {
+        Some(path) => Config::open(&current_dir.join(path), true)?,
+        None => Config::open(
+            &root.as_deref().unwrap_or(&current_dir).join(CONFIG_FILE),
+            false,
+        )?,
+    };
+    let root = root.or_else(|| config.root.clone()).unwrap_or(current_dir);
+    let changes = FileChanges {
+        dry_run: args.dry_run,
+        backup: args.backup,
+    };
+    args.commands.execute(&root, changes, &config)
+}
+
+fn main() {
+    let args = match TranscriptionCLI::try_parse() {
+        Ok(args) => args,
+        Err(error) => {
+            // Printing the help or the version is not an error.
+            let _ = error.print();
+            let code = if error.use_stderr() {
+                FailureKind::Usage.exit_code()
+            } else {
+                0
+            };
+            process::exit(code);
+        }
+    };
+    if let Err(error) = run(args) {
+        eprintln!("Error: {error:?}");
+        process::exit(exit::exit_code(&error));
+    }
 }
 
 #[cfg(test)]
@@ -213,7 +2163,7 @@ mod test {
     #[test]
     fn test_verify_courses() -> Result<()> {
         let trane = Trane::new_local(&std::env::current_dir()?, &std::env::current_dir()?)?;
-        assert!(trane.get_all_exercise_ids(None).len() > 0);
+        assert!(!trane.get_all_exercise_ids(None).is_empty());
         Ok(())
     }
 }
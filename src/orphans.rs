@@ -0,0 +1,123 @@
+//! Contains the `orphans` subcommand, which finds files left behind in the passage directories of
+//! the courses. trane only reads the JSON files directly inside the passage directory, so any other
+//! file in it is not part of the course.
+
+use std::path::Path;
+
+use anyhow::{bail, Context, Result};
+use walkdir::WalkDir;
+
+use crate::{
+    exit::{self, Failure, FailureKind},
+    library,
+    output::status,
+};
+
+/// Returns whether trane reads the file at the given path, relative to the passage directory, as a
+/// passage.
+fn is_passage_file(relative_path: &Path) -> bool {
+    relative_path.components().count() == 1
+        && relative_path
+            .extension()
+            .is_some_and(|extension| extension.eq_ignore_ascii_case("json"))
+}
+
+/// Returns the paths, relative to the passage directory, of all the files in the directory that
+/// trane does not read as passages, sorted alphabetically.
+fn find_orphaned_files(passage_directory: &Path) -> Result<Vec<String>> {
+    let mut orphans = vec![];
+    for entry in WalkDir::new(passage_directory).min_depth(1) {
+        let entry = entry.with_context(|| {
+            format!(
+                "failed to read passage directory at {}",
+                passage_directory.display()
+            )
+        })?;
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let relative_path = entry
+            .path()
+            .strip_prefix(passage_directory)
+            .unwrap_or(entry.path());
+        if !is_passage_file(relative_path) {
+            orphans.push(relative_path.display().to_string());
+        }
+    }
+    orphans.sort();
+    Ok(orphans)
+}
+
+/// Prints the files in the passage directory of each course that are not read as passages. If
+/// `fail` is true, returns an error if any are found.
+pub(crate) fn report_orphaned_files(root: &Path, fail: bool) -> Result<()> {
+    let courses_root = library::courses_root(root)?;
+    let mut total = 0;
+    for (path, manifest) in library::read_all_manifests(&courses_root)? {
+        let Some(config) = library::transcription_config(&manifest) else {
+            continue;
+        };
+        if config.passage_directory.is_empty() {
+            continue;
+        }
+
+        // Missing passage directories are reported by `verify-courses`.
+        let course_directory = path.parent().unwrap_or(Path::new("."));
+        let passage_directory = course_directory.join(&config.passage_directory);
+        if !passage_directory.is_dir() {
+            continue;
+        }
+        let orphans = find_orphaned_files(&passage_directory)?;
+        if orphans.is_empty() {
+            continue;
+        }
+        total += orphans.len();
+        println!(
+            "Course {} has {} orphaned files in {}:",
+            manifest.id,
+            orphans.len(),
+            passage_directory.display()
+        );
+        for orphan in orphans {
+            println!("  {orphan}");
+        }
+    }
+
+    if total == 0 {
+        status!("No orphaned passage files found.");
+    } else if fail {
+        bail!(Failure::new(
+            FailureKind::InvalidCourses,
+            format!("found {total} orphaned passage files")
+        ));
+    }
+    exit::fail_on_warnings(
+        FailureKind::InvalidCourses,
+        &[(total, "orphaned passage files")],
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use anyhow::Result;
+    use std::fs;
+
+    use super::find_orphaned_files;
+
+    /// Verifies that only the JSON files directly inside the passage directory are not orphaned.
+    #[test]
+    fn orphaned_files() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        fs::write(dir.path().join("dragon_eyes.json"), "{}")?;
+        fs::write(dir.path().join("zombie_girl.JSON"), "{}")?;
+        fs::write(dir.path().join("notes.txt"), "")?;
+        fs::create_dir(dir.path().join("old"))?;
+        fs::write(dir.path().join("old").join("removed.json"), "{}")?;
+        assert_eq!(
+            find_orphaned_files(dir.path())?,
+            vec!["notes.txt".to_string(), "old/removed.json".to_string()]
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,147 @@
+//! Contains the global `--quiet` setting, which silences the informational messages printed when
+//! a command succeeds. Errors, warnings, and the output requested from a command are not affected.
+//! Also contains the global `--color` setting checked by every command that prints colored text,
+//! and the helper used by the report commands to write their output to a file.
+
+use std::{
+    fs,
+    io::{self, IsTerminal},
+    path::Path,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+
+use crate::library;
+
+/// Whether informational messages are silenced.
+static QUIET: AtomicBool = AtomicBool::new(false);
+
+/// Whether the output is colored.
+static COLOR: AtomicBool = AtomicBool::new(false);
+
+/// When to color the output.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub(crate) enum ColorChoice {
+    /// Color the output only if stdout is a terminal.
+    #[default]
+    Auto,
+
+    /// Always color the output.
+    Always,
+
+    /// Never color the output.
+    Never,
+}
+
+/// A color used in the output.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Color {
+    /// Used for additions and success.
+    Green,
+
+    /// Used for removals and errors.
+    Red,
+
+    /// Used for changes and warnings.
+    Yellow,
+}
+
+impl Color {
+    /// Returns the ANSI escape code that switches to the color.
+    fn code(self) -> &'static str {
+        match self {
+            Color::Green => "\x1b[32m",
+            Color::Red => "\x1b[31m",
+            Color::Yellow => "\x1b[33m",
+        }
+    }
+}
+
+/// Sets whether the output is colored, resolving `auto` based on whether stdout is a terminal.
+pub(crate) fn set_color(choice: ColorChoice) {
+    let color = match choice {
+        ColorChoice::Auto => io::stdout().is_terminal(),
+        ColorChoice::Always => true,
+        ColorChoice::Never => false,
+    };
+    COLOR.store(color, Ordering::Relaxed);
+}
+
+/// Returns the text in the given color, or unchanged if the output is not colored.
+pub(crate) fn paint(text: &str, color: Color) -> String {
+    if COLOR.load(Ordering::Relaxed) {
+        format!("{}{text}\x1b[0m", color.code())
+    } else {
+        text.to_string()
+    }
+}
+
+/// Sets whether informational messages are silenced.
+pub(crate) fn set_quiet(quiet: bool) {
+    QUIET.store(quiet, Ordering::Relaxed);
+}
+
+/// Returns whether informational messages are silenced.
+pub(crate) fn is_quiet() -> bool {
+    QUIET.load(Ordering::Relaxed)
+}
+
+/// Prints an informational message to stdout, like `println!`, unless `--quiet` was given.
+macro_rules! status {
+    ($($arg:tt)*) => {
+        if !$crate::output::is_quiet() {
+            println!($($arg)*);
+        }
+    };
+}
+
+pub(crate) use status;
+
+/// Writes the report to the given file, creating its parent directories if needed, or prints it to
+/// stdout if no file is given. The file is written atomically, so it never contains a partial
+/// report.
+pub(crate) fn write_report(report: &str, output_file: Option<&Path>) -> Result<()> {
+    let Some(path) = output_file else {
+        print!("{report}");
+        return Ok(());
+    };
+    if let Some(directory) = path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+    {
+        fs::create_dir_all(directory)
+            .with_context(|| format!("failed to create directory at {}", directory.display()))?;
+    }
+    library::write_atomically(path, report.as_bytes(), false)?;
+    status!("Wrote the report to {}.", path.display());
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use anyhow::Result;
+    use std::fs;
+
+    use super::{paint, set_color, write_report, Color, ColorChoice};
+
+    /// Verifies that text is only colored when colors are enabled.
+    #[test]
+    fn paint_text() {
+        set_color(ColorChoice::Always);
+        assert_eq!(paint("+ added", Color::Green), "\x1b[32m+ added\x1b[0m");
+        set_color(ColorChoice::Never);
+        assert_eq!(paint("+ added", Color::Green), "+ added");
+    }
+
+    /// Verifies that the report is written to the file, creating its parent directories.
+    #[test]
+    fn write_report_to_file() -> Result<()> {
+        let directory = tempfile::tempdir()?;
+        let path = directory.path().join("reports/links.json");
+        write_report("{}\n", Some(&path))?;
+        assert_eq!(fs::read_to_string(&path)?, "{}\n");
+        Ok(())
+    }
+}
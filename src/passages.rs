@@ -0,0 +1,518 @@
+//! Contains the subcommands that list and edit the inlined passages of a transcription course.
+
+use std::{collections::HashMap, path::Path};
+
+use anyhow::{bail, Context, Result};
+use serde::Serialize;
+use trane::data::{
+    course_generator::transcription::{
+        TranscriptionAsset, TranscriptionConfig, TranscriptionLink, TranscriptionPassages,
+    },
+    CourseGenerator,
+};
+use ustr::Ustr;
+
+use crate::{
+    changes::FileChanges,
+    checks,
+    exit::{Failure, FailureKind},
+    library,
+    links::{self, ExternalLink, VerifyLinksOptions},
+    output::status,
+    reports::ReportFormat,
+};
+
+/// An external link of a passage printed by the `passages` subcommand.
+#[derive(Debug, Serialize)]
+struct LinkSummary {
+    /// The URL of the link.
+    link: String,
+
+    /// The platform hosting the link.
+    platform: &'static str,
+}
+
+impl From<&ExternalLink> for LinkSummary {
+    fn from(link: &ExternalLink) -> Self {
+        Self {
+            link: link.url().to_string(),
+            platform: link.platform_name(),
+        }
+    }
+}
+
+/// An inlined passage printed by the `passages` subcommand.
+#[derive(Debug, Serialize)]
+struct PassageSummary {
+    /// The position of the passage in the course, starting at 1.
+    number: usize,
+
+    /// The short ID of the passage.
+    short_id: String,
+
+    /// The external link of the passage, if any.
+    link: Option<String>,
+
+    /// The platform hosting the external link, if any.
+    platform: Option<&'static str>,
+
+    /// The links to the same track on other platforms.
+    additional_links: Vec<LinkSummary>,
+}
+
+/// Prints the inlined passages of the course in the given format.
+pub(crate) fn list_passages(root: &Path, id: &str, format: ReportFormat) -> Result<()> {
+    let courses_root = library::courses_root(root)?;
+    let (_, manifest) = library::read_course(&courses_root, id)?;
+    let Some(config) = library::transcription_config(&manifest) else {
+        bail!("course {} is not a transcription course", manifest.id);
+    };
+    let additional_links = library::read_additional_links(&courses_root, &manifest.id)?;
+
+    let summaries = config
+        .inlined_passages
+        .iter()
+        .enumerate()
+        .map(|(index, passages)| {
+            let TranscriptionAsset::Track { external_link, .. } = &passages.asset;
+            let link = external_link.as_ref().map(ExternalLink::from);
+            PassageSummary {
+                number: index + 1,
+                short_id: passages.asset.short_id().to_string(),
+                link: link.as_ref().map(|link| link.url().to_string()),
+                platform: link.as_ref().map(ExternalLink::platform_name),
+                additional_links: library::passage_links(&additional_links, passages)
+                    .iter()
+                    .skip(usize::from(link.is_some()))
+                    .map(LinkSummary::from)
+                    .collect(),
+            }
+        })
+        .collect::<Vec<_>>();
+
+    match format {
+        ReportFormat::Plain => {
+            for summary in &summaries {
+                match (&summary.link, summary.platform) {
+                    (Some(link), Some(platform)) => println!(
+                        "{}. {}: {link} ({platform})",
+                        summary.number, summary.short_id
+                    ),
+                    _ => println!("{}. {}: no link", summary.number, summary.short_id),
+                }
+                for additional_link in &summary.additional_links {
+                    println!(
+                        "   also: {} ({})",
+                        additional_link.link, additional_link.platform
+                    );
+                }
+            }
+        }
+        ReportFormat::Json => println!("{}", serde_json::to_string_pretty(&summaries)?),
+    }
+    Ok(())
+}
+
+/// The details of a passage added by the `add-passage` subcommand.
+pub(crate) struct NewPassage {
+    /// The short ID of the passage.
+    pub short_id: String,
+
+    /// The name of the track. Defaults to the short ID.
+    pub track_name: Option<String>,
+
+    /// The name of the artist who performs the track.
+    pub artist_name: Option<String>,
+
+    /// The external link of the track.
+    pub external_link: Option<TranscriptionLink>,
+
+    /// The links to the same track on other platforms, stored in the additional links file of the
+    /// course.
+    pub additional_links: Vec<String>,
+}
+
+/// Appends a passage covering the whole track to the inlined passages of the course. Each of the
+/// external links is verified independently with the given options first unless `skip_verify` is
+/// true.
+pub(crate) fn add_passage(
+    root: &Path,
+    changes: FileChanges,
+    course: &str,
+    passage: NewPassage,
+    skip_verify: bool,
+    options: &VerifyLinksOptions,
+) -> Result<()> {
+    let courses_root = library::courses_root(root)?;
+    let (path, mut manifest) = library::read_course(&courses_root, course)?;
+    let course_id = manifest.id;
+    let Some(CourseGenerator::Transcription(config)) = &mut manifest.generator_config else {
+        bail!("course {course_id} is not a transcription course");
+    };
+    if !checks::is_valid_short_id(&passage.short_id) {
+        bail!("invalid short ID \"{}\"", passage.short_id);
+    }
+    if config
+        .inlined_passages
+        .iter()
+        .any(|passages| passages.asset.short_id() == passage.short_id)
+    {
+        bail!(
+            "course {course_id} already has a passage with short ID {}",
+            passage.short_id
+        );
+    }
+    if !skip_verify {
+        let links = passage.external_link.iter().map(ExternalLink::from).chain(
+            passage
+                .additional_links
+                .iter()
+                .map(|url| ExternalLink::from(url.as_str())),
+        );
+        for link in links {
+            links::verify_link(&link, options).with_context(|| {
+                format!(
+                    "failed to verify the link {} of the passage, use --skip-verify to add it \
+                    anyway",
+                    link.url()
+                )
+            })?;
+            status!("Verified the {} link {}", link.platform_name(), link.url());
+        }
+    }
+
+    config.inlined_passages.push(TranscriptionPassages {
+        asset: TranscriptionAsset::Track {
+            track_name: passage
+                .track_name
+                .unwrap_or_else(|| passage.short_id.clone()),
+            short_id: passage.short_id.clone(),
+            artist_name: passage.artist_name,
+            album_name: None,
+            duration: None,
+            external_link: passage.external_link,
+        },
+        intervals: HashMap::new(),
+    });
+    changes.write_manifest(&path, &manifest)?;
+    if !passage.additional_links.is_empty() {
+        let mut additional_links = library::read_additional_links(&courses_root, &course_id)?;
+        additional_links.insert(passage.short_id.clone(), passage.additional_links);
+        changes.write_additional_links(
+            &library::additional_links_path(&courses_root, &course_id),
+            &additional_links,
+        )?;
+    }
+    status!("Added passage {} to course {course_id}", passage.short_id);
+    Ok(())
+}
+
+/// Removes the inlined passage with the given short ID from the course.
+pub(crate) fn remove_passage(
+    root: &Path,
+    changes: FileChanges,
+    course: &str,
+    short_id: &str,
+) -> Result<()> {
+    let courses_root = library::courses_root(root)?;
+    let (path, mut manifest) = library::read_course(&courses_root, course)?;
+    let course_id = manifest.id;
+    let Some(CourseGenerator::Transcription(config)) = &mut manifest.generator_config else {
+        bail!("course {course_id} is not a transcription course");
+    };
+    let Some(position) = config
+        .inlined_passages
+        .iter()
+        .position(|passages| passages.asset.short_id() == short_id)
+    else {
+        bail!("course {course_id} has no passage with short ID {short_id}");
+    };
+
+    config.inlined_passages.remove(position);
+    let remaining = config.inlined_passages.len();
+    changes.write_manifest(&path, &manifest)?;
+    let mut additional_links = library::read_additional_links(&courses_root, &course_id)?;
+    if additional_links.remove(short_id).is_some() {
+        changes.write_additional_links(
+            &library::additional_links_path(&courses_root, &course_id),
+            &additional_links,
+        )?;
+    }
+    status!("Removed passage {short_id} from course {course_id}, {remaining} passages remain");
+    Ok(())
+}
+
+/// Replaces every link of a track to the same video as `old` with `new`, including the additional
+/// links of each passage. The new link is verified with the given options first unless
+/// `skip_verify` is true.
+pub(crate) fn replace_link(
+    root: &Path,
+    changes: FileChanges,
+    old: &str,
+    new: &str,
+    skip_verify: bool,
+    options: &VerifyLinksOptions,
+) -> Result<()> {
+    let new_link = TranscriptionLink::YouTube(new.to_string());
+    if !skip_verify {
+        links::verify_link(&ExternalLink::from(&new_link), options)
+            .context("failed to verify the new link, use --skip-verify to replace it anyway")?;
+    }
+
+    let canonical_old = links::canonical_link(old);
+    let mut replaced = 0;
+    let courses_root = library::courses_root(root)?;
+    for (path, mut manifest) in library::read_all_manifests(&courses_root)? {
+        let course_id = manifest.id;
+        let original_manifest = manifest.clone();
+        let Some(CourseGenerator::Transcription(config)) = &mut manifest.generator_config else {
+            continue;
+        };
+        let mut additional_links = library::read_additional_links(&courses_root, &course_id)?;
+        let original_links = additional_links.clone();
+        for passages in &mut config.inlined_passages {
+            let short_id = passages.asset.short_id().to_string();
+            for url in library::passage_links_mut(&mut additional_links, passages) {
+                if links::canonical_link(url) == canonical_old {
+                    *url = new_link.url().to_string();
+                    replaced += 1;
+                    status!("Updated the link of course {course_id}, asset {short_id}");
+                }
+            }
+        }
+        if manifest != original_manifest {
+            changes.write_manifest(&path, &manifest)?;
+        }
+        if additional_links != original_links {
+            changes.write_additional_links(
+                &library::additional_links_path(&courses_root, &course_id),
+                &additional_links,
+            )?;
+        }
+    }
+
+    if replaced == 0 {
+        bail!("no passages link to {old}");
+    }
+    status!("Replaced {replaced} links.");
+    Ok(())
+}
+
+/// A passage linking to the video searched by the `where-used` subcommand.
+#[derive(Debug, Serialize)]
+struct LinkUsage {
+    /// The ID of the course containing the passage.
+    course_id: Ustr,
+
+    /// The short ID of the passage.
+    short_id: String,
+
+    /// The link of the passage, as written in the manifest.
+    link: String,
+}
+
+/// Prints every passage with a link to the same video as `link` in the given format. YouTube links
+/// match any link to the same video, including the additional links of each passage.
+pub(crate) fn where_used(root: &Path, link: &str, format: ReportFormat) -> Result<()> {
+    let canonical = links::canonical_link(link);
+    let mut usages = vec![];
+    let courses_root = library::courses_root(root)?;
+    for (_, manifest) in library::read_all_manifests(&courses_root)? {
+        let Some(config) = library::transcription_config(&manifest) else {
+            continue;
+        };
+        let additional_links = library::read_additional_links(&courses_root, &manifest.id)?;
+        for passages in &config.inlined_passages {
+            for passage_link in library::passage_links(&additional_links, passages) {
+                if links::canonical_link(passage_link.url()) == canonical {
+                    usages.push(LinkUsage {
+                        course_id: manifest.id,
+                        short_id: passages.asset.short_id().to_string(),
+                        link: passage_link.url().to_string(),
+                    });
+                }
+            }
+        }
+    }
+
+    match format {
+        ReportFormat::Plain => {
+            if usages.is_empty() {
+                status!("No passages link to {link}.");
+            }
+            for usage in &usages {
+                println!(
+                    "Course {}, asset {}: {}",
+                    usage.course_id, usage.short_id, usage.link
+                );
+            }
+        }
+        ReportFormat::Json => println!("{}", serde_json::to_string_pretty(&usages)?),
+    }
+    Ok(())
+}
+
+/// Rewrites the YouTube links of every track, including the additional links of each passage, to
+/// the canonical `https://www.youtube.com/watch?v=<id>` form, keeping their timestamps. If `check`
+/// is true, the links that need to be normalized are reported but not rewritten, and an error is
+/// returned if there are any.
+pub(crate) fn normalize_links(root: &Path, changes: FileChanges, check: bool) -> Result<()> {
+    let mut normalized = 0;
+    let courses_root = library::courses_root(root)?;
+    for (path, mut manifest) in library::read_all_manifests(&courses_root)? {
+        let course_id = manifest.id;
+        let original_manifest = manifest.clone();
+        let Some(CourseGenerator::Transcription(config)) = &mut manifest.generator_config else {
+            continue;
+        };
+        let mut additional_links = library::read_additional_links(&courses_root, &course_id)?;
+        let original_links = additional_links.clone();
+        for passages in &mut config.inlined_passages {
+            let short_id = passages.asset.short_id().to_string();
+            for url in library::passage_links_mut(&mut additional_links, passages) {
+                let Some(normalized_url) = links::normalize_youtube_link(url) else {
+                    continue;
+                };
+                if normalized_url == *url {
+                    continue;
+                }
+
+                normalized += 1;
+                if check {
+                    println!(
+                        "Course {course_id}, asset {short_id}: {url} should be {normalized_url}"
+                    );
+                } else {
+                    status!("Course {course_id}, asset {short_id}: {url} -> {normalized_url}");
+                    *url = normalized_url;
+                }
+            }
+        }
+        if manifest != original_manifest {
+            changes.write_manifest(&path, &manifest)?;
+        }
+        if additional_links != original_links {
+            changes.write_additional_links(
+                &library::additional_links_path(&courses_root, &course_id),
+                &additional_links,
+            )?;
+        }
+    }
+
+    if check {
+        if normalized > 0 {
+            bail!(Failure::new(
+                FailureKind::InvalidCourses,
+                format!("found {normalized} links that need to be normalized")
+            ));
+        }
+        status!("All links are normalized.");
+    } else {
+        status!("Normalized {normalized} links.");
+    }
+    Ok(())
+}
+
+/// Sorts the inlined passages of the course by short ID and returns whether their order changed.
+/// The sort is stable, so passages with the same short ID keep their relative order.
+pub(crate) fn sort_inlined_passages(config: &mut TranscriptionConfig) -> bool {
+    let is_sorted = config
+        .inlined_passages
+        .windows(2)
+        .all(|pair| pair[0].asset.short_id() <= pair[1].asset.short_id());
+    if is_sorted {
+        return false;
+    }
+    config
+        .inlined_passages
+        .sort_by(|a, b| a.asset.short_id().cmp(b.asset.short_id()));
+    true
+}
+
+/// Sorts the inlined passages of every transcription course by short ID. If `check` is true, the
+/// courses whose passages are not sorted are reported but not rewritten, and an error is returned
+/// if there are any.
+pub(crate) fn sort_passages(root: &Path, changes: FileChanges, check: bool) -> Result<()> {
+    let mut unsorted = 0;
+    for (path, mut manifest) in library::read_all_manifests(&library::courses_root(root)?)? {
+        let course_id = manifest.id;
+        let Some(CourseGenerator::Transcription(config)) = &mut manifest.generator_config else {
+            continue;
+        };
+        if !sort_inlined_passages(config) {
+            continue;
+        }
+
+        unsorted += 1;
+        if check {
+            println!("Course {course_id}: the inlined passages are not sorted by short ID");
+        } else {
+            changes.write_manifest(&path, &manifest)?;
+            status!("Sorted the inlined passages of course {course_id}");
+        }
+    }
+
+    if check {
+        if unsorted > 0 {
+            bail!(Failure::new(
+                FailureKind::InvalidCourses,
+                format!("found {unsorted} courses whose passages are not sorted")
+            ));
+        }
+        status!("The passages of all courses are sorted.");
+    } else {
+        status!("Sorted the passages of {unsorted} courses.");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use trane::data::course_generator::transcription::{
+        TranscriptionAsset, TranscriptionConfig, TranscriptionPassages,
+    };
+
+    use super::sort_inlined_passages;
+
+    /// Returns a passage of the track with the given short and track names.
+    fn passage(short_id: &str, track_name: &str) -> TranscriptionPassages {
+        TranscriptionPassages {
+            asset: TranscriptionAsset::Track {
+                short_id: short_id.to_string(),
+                track_name: track_name.to_string(),
+                artist_name: None,
+                album_name: None,
+                duration: None,
+                external_link: None,
+            },
+            intervals: Default::default(),
+        }
+    }
+
+    /// Verifies that passages are sorted by short ID, keeping the order of duplicates.
+    #[test]
+    fn sort_passages_by_short_id() {
+        let mut config = TranscriptionConfig {
+            inlined_passages: vec![
+                passage("b", "first"),
+                passage("a", "second"),
+                passage("b", "third"),
+            ],
+            passage_directory: String::new(),
+            transcription_dependencies: vec![],
+            skip_singing_lessons: false,
+            skip_advanced_lessons: false,
+        };
+        assert!(sort_inlined_passages(&mut config));
+        let names = config
+            .inlined_passages
+            .iter()
+            .map(|passages| {
+                let TranscriptionAsset::Track { track_name, .. } = &passages.asset;
+                track_name.as_str()
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec!["second", "first", "third"]);
+        assert!(!sort_inlined_passages(&mut config));
+    }
+}
@@ -0,0 +1,110 @@
+//! Contains simple prompts to read the answers to questions from the user.
+
+use std::io::{BufRead, Write};
+
+use anyhow::{bail, Result};
+
+/// Asks the question and returns the answer with surrounding whitespace removed. The default value
+/// is shown in brackets.
+fn ask(
+    input: &mut impl BufRead,
+    output: &mut impl Write,
+    question: &str,
+    default: &str,
+) -> Result<String> {
+    write!(output, "{question} [{default}]: ")?;
+    output.flush()?;
+    let mut answer = String::new();
+    input.read_line(&mut answer)?;
+    Ok(answer.trim().to_string())
+}
+
+/// Asks for a comma-separated list of values. An empty answer keeps the default values.
+pub(crate) fn prompt_list(
+    input: &mut impl BufRead,
+    output: &mut impl Write,
+    question: &str,
+    default: &[String],
+) -> Result<Vec<String>> {
+    let answer = ask(input, output, question, &default.join(", "))?;
+    if answer.is_empty() {
+        return Ok(default.to_vec());
+    }
+    Ok(answer
+        .split(',')
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .map(str::to_string)
+        .collect())
+}
+
+/// Asks for an optional value. An empty answer keeps the default value, if any.
+pub(crate) fn prompt_optional(
+    input: &mut impl BufRead,
+    output: &mut impl Write,
+    question: &str,
+    default: Option<&str>,
+) -> Result<Option<String>> {
+    let answer = ask(input, output, question, default.unwrap_or_default())?;
+    if answer.is_empty() {
+        return Ok(default.map(str::to_string));
+    }
+    Ok(Some(answer))
+}
+
+/// Asks a yes or no question. An empty answer keeps the default value.
+pub(crate) fn prompt_bool(
+    input: &mut impl BufRead,
+    output: &mut impl Write,
+    question: &str,
+    default: bool,
+) -> Result<bool> {
+    let default_answer = if default { "y" } else { "n" };
+    let answer = ask(input, output, question, default_answer)?;
+    match answer.to_lowercase().as_str() {
+        "" => Ok(default),
+        "y" | "yes" => Ok(true),
+        "n" | "no" => Ok(false),
+        _ => bail!("invalid answer {answer}, expected y or n"),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use anyhow::Result;
+
+    use super::*;
+
+    /// Verifies that empty answers keep the default values and that other answers are parsed.
+    #[test]
+    fn prompt_answers() -> Result<()> {
+        let mut input = "\na, b ,,c\n\nyes\nmaybe\n\n\n Name \n".as_bytes();
+        let mut output = vec![];
+        let default = vec!["default".to_string()];
+        assert_eq!(
+            prompt_list(&mut input, &mut output, "List", &default)?,
+            default
+        );
+        assert_eq!(
+            prompt_list(&mut input, &mut output, "List", &default)?,
+            vec!["a", "b", "c"]
+        );
+        assert!(!prompt_bool(&mut input, &mut output, "Bool", false)?);
+        assert!(prompt_bool(&mut input, &mut output, "Bool", false)?);
+        assert!(prompt_bool(&mut input, &mut output, "Bool", false).is_err());
+        assert_eq!(
+            prompt_optional(&mut input, &mut output, "Optional", None)?,
+            None
+        );
+        assert_eq!(
+            prompt_optional(&mut input, &mut output, "Optional", Some("default"))?,
+            Some("default".to_string())
+        );
+        assert_eq!(
+            prompt_optional(&mut input, &mut output, "Optional", None)?,
+            Some("Name".to_string())
+        );
+        assert!(String::from_utf8(output)?.starts_with("List [default]: "));
+        Ok(())
+    }
+}
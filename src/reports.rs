@@ -0,0 +1,474 @@
+//! Contains the subcommands that print reports about the courses in the library.
+
+use std::{collections::BTreeMap, fmt::Write, path::Path};
+
+use anyhow::Result;
+use clap::ValueEnum;
+use serde::Serialize;
+use trane::{
+    course_library::CourseLibrary,
+    data::{
+        course_generator::transcription::{
+            TranscriptionAsset, TranscriptionConfig, TranscriptionPassages,
+        },
+        CourseManifest,
+    },
+    Trane,
+};
+use ustr::Ustr;
+
+use crate::{
+    library::{self, AdditionalLinks},
+    links::ExternalLink,
+    output,
+};
+
+/// The format used to print the output of a command.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub(crate) enum OutputFormat {
+    /// Human-readable text.
+    #[default]
+    Plain,
+
+    /// A JSON document.
+    Json,
+
+    /// Comma-separated values with a header row.
+    Csv,
+}
+
+/// The format used to print a report that is not tabular, and therefore cannot be printed as CSV.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub(crate) enum ReportFormat {
+    /// Human-readable text.
+    #[default]
+    Plain,
+
+    /// A JSON document.
+    Json,
+}
+
+/// A summary of a transcription course printed by the `list` subcommand.
+#[derive(Debug, Serialize)]
+struct CourseSummary {
+    /// The ID of the course.
+    id: Ustr,
+
+    /// The number of inlined passages in the course.
+    passage_count: usize,
+
+    /// The number of transcription dependencies of the course.
+    dependency_count: usize,
+}
+
+/// Lists the transcription courses in the library selected by the `--only` filter in the given
+/// format.
+pub(crate) fn list_courses(root: &Path, only: &[Ustr], format: OutputFormat) -> Result<()> {
+    // Open the trane-transcription library in trane.
+    let trane = library::open_library(root)?;
+
+    // Summarize each selected transcription course.
+    library::warn_unknown_courses(only, &trane.get_course_ids());
+    let summaries = library::transcription_courses(&trane)
+        .into_iter()
+        .filter(|(manifest, _)| library::is_selected(only, manifest.id))
+        .map(|(manifest, config)| CourseSummary {
+            id: manifest.id,
+            passage_count: config.inlined_passages.len(),
+            dependency_count: config.transcription_dependencies.len(),
+        })
+        .collect::<Vec<_>>();
+
+    match format {
+        OutputFormat::Plain => {
+            for summary in &summaries {
+                println!(
+                    "{}: {} passages, {} dependencies",
+                    summary.id, summary.passage_count, summary.dependency_count
+                );
+            }
+        }
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&summaries)?),
+        OutputFormat::Csv => {
+            println!("id,passage_count,dependency_count");
+            for summary in &summaries {
+                println!(
+                    "{},{},{}",
+                    summary.id, summary.passage_count, summary.dependency_count
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Statistics about the transcription courses printed by the `stats` subcommand.
+#[derive(Debug, Default, Serialize)]
+struct LibraryStats {
+    /// The number of transcription courses.
+    course_count: usize,
+
+    /// The number of transcription courses left out by the `--exclude` filter.
+    #[serde(skip_serializing_if = "is_zero")]
+    excluded_count: usize,
+
+    /// The total number of inlined passages.
+    passage_count: usize,
+
+    /// The number of courses that skip the singing lessons.
+    skip_singing_lessons_count: usize,
+
+    /// The number of courses that skip the advanced lessons.
+    skip_advanced_lessons_count: usize,
+
+    /// The average number of transcription dependencies per course.
+    average_dependencies: f64,
+
+    /// A mapping of number of passages to the number of courses with that many passages.
+    passages_histogram: BTreeMap<usize, usize>,
+
+    /// A mapping of the kind of asset to the number of inlined passages using that kind of asset.
+    asset_types: BTreeMap<&'static str, usize>,
+
+    /// The number of tracks with an external link.
+    tracks_with_links: usize,
+
+    /// The number of tracks without an external link.
+    tracks_without_links: usize,
+
+    /// A mapping of the name of a platform to the number of tracks linking to it.
+    links_per_platform: BTreeMap<&'static str, usize>,
+}
+
+impl LibraryStats {
+    /// Adds the asset of an inlined passage to the breakdown of assets by type and platform. The
+    /// additional links of the passage are counted along with its external link.
+    fn add_passage(
+        &mut self,
+        passages: &TranscriptionPassages,
+        additional_links: &AdditionalLinks,
+    ) {
+        match &passages.asset {
+            TranscriptionAsset::Track { .. } => {
+                *self.asset_types.entry("track").or_default() += 1;
+                let mut platforms = library::passage_links(additional_links, passages)
+                    .iter()
+                    .map(ExternalLink::platform_name)
+                    .collect::<Vec<_>>();
+                if platforms.is_empty() {
+                    self.tracks_without_links += 1;
+                    return;
+                }
+                self.tracks_with_links += 1;
+                platforms.sort_unstable();
+                platforms.dedup();
+                for platform in platforms {
+                    *self.links_per_platform.entry(platform).or_default() += 1;
+                }
+            }
+        }
+    }
+}
+
+/// Returns the transcription courses in the library selected by the `--only` filter and not removed
+/// by the `--exclude` filter, along with the number of courses removed by the latter. Warns about
+/// the patterns that match no course.
+fn selected_transcription_courses(
+    trane: &Trane,
+    only: &[Ustr],
+    exclude: &[Ustr],
+) -> (Vec<(CourseManifest, TranscriptionConfig)>, usize) {
+    library::warn_unknown_courses(only, &trane.get_course_ids());
+    library::warn_unknown_courses(exclude, &trane.get_course_ids());
+    let (excluded, selected): (Vec<_>, Vec<_>) = library::transcription_courses(trane)
+        .into_iter()
+        .filter(|(manifest, _)| library::is_selected(only, manifest.id))
+        .partition(|(manifest, _)| library::is_excluded(exclude, manifest.id));
+    (selected, excluded.len())
+}
+
+/// Returns whether the count is zero, so it can be left out of the JSON output.
+fn is_zero(count: &usize) -> bool {
+    *count == 0
+}
+
+/// Prints statistics about the transcription courses in the library selected by the `--only` and
+/// `--exclude` filters in the given format, or writes them to `output_file` if given.
+pub(crate) fn print_stats(
+    root: &Path,
+    only: &[Ustr],
+    exclude: &[Ustr],
+    format: ReportFormat,
+    output_file: Option<&Path>,
+) -> Result<()> {
+    // Open the trane-transcription library in trane.
+    let trane = library::open_library(root)?;
+    let courses_root = library::courses_root(root)?;
+
+    // Aggregate the statistics of each transcription course.
+    let mut stats = LibraryStats::default();
+    let mut dependency_count = 0;
+    let (courses, excluded_count) = selected_transcription_courses(&trane, only, exclude);
+    stats.excluded_count = excluded_count;
+    for (manifest, config) in courses {
+        stats.course_count += 1;
+        stats.passage_count += config.inlined_passages.len();
+        stats.skip_singing_lessons_count += usize::from(config.skip_singing_lessons);
+        stats.skip_advanced_lessons_count += usize::from(config.skip_advanced_lessons);
+        *stats
+            .passages_histogram
+            .entry(config.inlined_passages.len())
+            .or_default() += 1;
+        dependency_count += config.transcription_dependencies.len();
+        let additional_links = library::read_additional_links(&courses_root, &manifest.id)?;
+        for passages in &config.inlined_passages {
+            stats.add_passage(passages, &additional_links);
+        }
+    }
+    if stats.course_count > 0 {
+        stats.average_dependencies = dependency_count as f64 / stats.course_count as f64;
+    }
+
+    let mut report = String::new();
+    match format {
+        ReportFormat::Plain => {
+            writeln!(report, "Courses: {}", stats.course_count)?;
+            if stats.excluded_count > 0 {
+                writeln!(report, "Excluded courses: {}", stats.excluded_count)?;
+            }
+            writeln!(report, "Passages: {}", stats.passage_count)?;
+            writeln!(
+                report,
+                "Courses skipping singing lessons: {}",
+                stats.skip_singing_lessons_count
+            )?;
+            writeln!(
+                report,
+                "Courses skipping advanced lessons: {}",
+                stats.skip_advanced_lessons_count
+            )?;
+            writeln!(
+                report,
+                "Average dependencies per course: {:.2}",
+                stats.average_dependencies
+            )?;
+            writeln!(report, "Passages per course:")?;
+            for (passages, courses) in &stats.passages_histogram {
+                writeln!(report, "  {passages} passages: {courses} courses")?;
+            }
+            writeln!(report, "Assets by type:")?;
+            for (asset_type, count) in &stats.asset_types {
+                writeln!(report, "  {asset_type}: {count}")?;
+            }
+            writeln!(
+                report,
+                "Tracks with links: {}, without links: {}",
+                stats.tracks_with_links, stats.tracks_without_links
+            )?;
+            writeln!(report, "Links per platform:")?;
+            for (platform, count) in &stats.links_per_platform {
+                writeln!(report, "  {platform}: {count}")?;
+            }
+        }
+        ReportFormat::Json => writeln!(report, "{}", serde_json::to_string_pretty(&stats)?)?,
+    }
+    output::write_report(&report, output_file)
+}
+
+/// The number of exercises generated by the library printed by the `count-exercises` subcommand.
+#[derive(Debug, Default, Serialize)]
+struct ExerciseCounts {
+    /// The total number of exercises in the library, or in the selected courses if the `--only`
+    /// filter was given.
+    total_exercises: usize,
+
+    /// A mapping of course ID to the number of exercises generated by the course. Only included if
+    /// the counts per course were requested.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    courses: Option<BTreeMap<Ustr, usize>>,
+}
+
+/// Prints the number of exercises generated by the library and, if `per_course` is true, by each of
+/// the transcription courses. If the `--only` filter is given, only the exercises of the selected
+/// transcription courses are counted.
+pub(crate) fn count_exercises(
+    root: &Path,
+    per_course: bool,
+    only: &[Ustr],
+    format: ReportFormat,
+) -> Result<()> {
+    // Open the trane-transcription library in trane.
+    let trane = library::open_library(root)?;
+
+    // The exercises of each course are only counted if they are printed or needed for the total.
+    library::warn_unknown_courses(only, &trane.get_course_ids());
+    let courses = (per_course || !only.is_empty()).then(|| {
+        library::transcription_courses(&trane)
+            .into_iter()
+            .filter(|(manifest, _)| library::is_selected(only, manifest.id))
+            .map(|(manifest, _)| {
+                let count = trane.get_all_exercise_ids(Some(manifest.id)).len();
+                (manifest.id, count)
+            })
+            .collect::<BTreeMap<_, _>>()
+    });
+    let counts = ExerciseCounts {
+        total_exercises: match &courses {
+            Some(courses) if !only.is_empty() => courses.values().sum(),
+            _ => trane.get_all_exercise_ids(None).len(),
+        },
+        courses: courses.filter(|_| per_course),
+    };
+
+    match format {
+        ReportFormat::Plain => {
+            for (course_id, count) in counts.courses.iter().flatten() {
+                println!("{course_id}: {count} exercises");
+            }
+            println!("Total: {} exercises", counts.total_exercises);
+        }
+        ReportFormat::Json => println!("{}", serde_json::to_string_pretty(&counts)?),
+    }
+    Ok(())
+}
+
+/// How fleshed out a transcription course is, based on the number of passages it contains.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum Completeness {
+    /// The course has no inlined passages and no passage directory.
+    Empty,
+
+    /// The course has fewer inlined passages than the threshold, or only a passage directory.
+    Partial,
+
+    /// The course has at least as many inlined passages as the threshold.
+    Complete,
+}
+
+/// Classifies the course given its transcription config. Courses with at least `threshold` inlined
+/// passages are complete.
+pub(crate) fn classify_course(config: &TranscriptionConfig, threshold: usize) -> Completeness {
+    let passages = config.inlined_passages.len();
+    if passages == 0 && config.passage_directory.is_empty() {
+        Completeness::Empty
+    } else if passages >= threshold && passages > 0 {
+        Completeness::Complete
+    } else {
+        Completeness::Partial
+    }
+}
+
+/// The completeness of the library printed by the `completeness` subcommand.
+#[derive(Debug, Default, Serialize)]
+struct CompletenessReport {
+    /// The minimum number of passages for a course to be complete.
+    threshold: usize,
+
+    /// The number of courses left out by the `--exclude` filter.
+    #[serde(skip_serializing_if = "is_zero")]
+    excluded_count: usize,
+
+    /// The number of empty courses.
+    empty_count: usize,
+
+    /// The number of partial courses.
+    partial_count: usize,
+
+    /// The number of complete courses.
+    complete_count: usize,
+
+    /// The IDs of the empty courses.
+    empty_courses: Vec<Ustr>,
+
+    /// The IDs of the partial courses.
+    partial_courses: Vec<Ustr>,
+}
+
+/// Classifies each transcription course selected by the `--only` and `--exclude` filters as empty,
+/// partial, or complete and prints the number of courses in each category along with the empty and
+/// partial courses, or writes them to `output_file` if given.
+pub(crate) fn print_completeness(
+    root: &Path,
+    threshold: usize,
+    only: &[Ustr],
+    exclude: &[Ustr],
+    format: ReportFormat,
+    output_file: Option<&Path>,
+) -> Result<()> {
+    // Open the trane-transcription library in trane.
+    let trane = library::open_library(root)?;
+
+    let (courses, excluded_count) = selected_transcription_courses(&trane, only, exclude);
+    let mut report = CompletenessReport {
+        threshold,
+        excluded_count,
+        ..Default::default()
+    };
+    for (manifest, config) in courses {
+        match classify_course(&config, threshold) {
+            Completeness::Empty => {
+                report.empty_count += 1;
+                report.empty_courses.push(manifest.id);
+            }
+            Completeness::Partial => {
+                report.partial_count += 1;
+                report.partial_courses.push(manifest.id);
+            }
+            Completeness::Complete => report.complete_count += 1,
+        }
+    }
+
+    let mut output = String::new();
+    match format {
+        ReportFormat::Plain => {
+            writeln!(output, "Empty courses: {}", report.empty_count)?;
+            writeln!(output, "Partial courses: {}", report.partial_count)?;
+            writeln!(
+                output,
+                "Complete courses (at least {threshold} passages): {}",
+                report.complete_count
+            )?;
+            if report.excluded_count > 0 {
+                writeln!(output, "Excluded courses: {}", report.excluded_count)?;
+            }
+            if !report.empty_courses.is_empty() {
+                writeln!(output, "Empty:")?;
+                for course_id in &report.empty_courses {
+                    writeln!(output, "  {course_id}")?;
+                }
+            }
+            if !report.partial_courses.is_empty() {
+                writeln!(output, "Partial:")?;
+                for course_id in &report.partial_courses {
+                    writeln!(output, "  {course_id}")?;
+                }
+            }
+        }
+        ReportFormat::Json => writeln!(output, "{}", serde_json::to_string_pretty(&report)?)?,
+    }
+    output::write_report(&output, output_file)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{classify_course, Completeness};
+    use crate::templates::CourseTemplate;
+
+    /// Verifies that courses are classified by the number of inlined passages and whether they
+    /// have a passage directory.
+    #[test]
+    fn classify_courses() {
+        let mut config = CourseTemplate::Blank.config();
+        assert_eq!(classify_course(&config, 5), Completeness::Empty);
+        assert_eq!(classify_course(&config, 0), Completeness::Empty);
+
+        config.passage_directory = "passages".to_string();
+        assert_eq!(classify_course(&config, 5), Completeness::Partial);
+
+        let mut config = CourseTemplate::Song.config();
+        assert_eq!(classify_course(&config, 5), Completeness::Partial);
+        config.inlined_passages = vec![config.inlined_passages[0].clone(); 5];
+        assert_eq!(classify_course(&config, 5), Completeness::Complete);
+    }
+}
@@ -0,0 +1,328 @@
+//! Contains the JSON Schema of the course manifests, which is embedded in the binary so external
+//! tools and editors can use it, and a validator for the subset of JSON Schema used by it. The
+//! validator reports every violation along with the path to the offending value, which is easier
+//! to act on than the first error returned by trane when it loads the library. Keywords outside the
+//! subset are rejected instead of ignored, so the schema cannot gain constraints the validator does
+//! not check.
+
+use std::{fs, path::Path};
+
+use anyhow::{bail, Context, Result};
+use serde_json::Value;
+
+use crate::{
+    exit::{Failure, FailureKind},
+    library,
+    output::status,
+};
+
+/// The JSON Schema of the course manifests.
+pub(crate) const COURSE_MANIFEST_SCHEMA: &str =
+    include_str!("../schema/course_manifest.schema.json");
+
+/// The keywords understood by the validator. Annotations without any effect on validation, such as
+/// `description`, are included so that the schema can document itself.
+const SUPPORTED_KEYWORDS: &[&str] = &[
+    "$schema",
+    "$id",
+    "$defs",
+    "$ref",
+    "title",
+    "description",
+    "type",
+    "minLength",
+    "minItems",
+    "maxItems",
+    "items",
+    "minProperties",
+    "maxProperties",
+    "required",
+    "properties",
+    "additionalProperties",
+];
+
+/// Fails if the schema, or any schema nested in it, uses a keyword not in `SUPPORTED_KEYWORDS` or a
+/// `type` other than a single type name, since the validator would silently skip it. `path` is the
+/// JSON pointer to the schema within the root schema.
+fn check_keywords(schema: &Value, path: &str) -> Result<()> {
+    let Some(object) = schema.as_object() else {
+        bail!("schema at {path} is not an object");
+    };
+    for (keyword, value) in object {
+        if !SUPPORTED_KEYWORDS.contains(&keyword.as_str()) {
+            bail!("schema at {path} uses the unsupported keyword \"{keyword}\"");
+        }
+        match (keyword.as_str(), value) {
+            ("type", value) if !value.is_string() => {
+                bail!("schema at {path} must have a single type name")
+            }
+            ("$defs" | "properties", Value::Object(schemas)) => {
+                for (name, nested) in schemas {
+                    check_keywords(nested, &format!("{path}/{keyword}/{name}"))?;
+                }
+            }
+            ("items", nested) | ("additionalProperties", nested @ Value::Object(_)) => {
+                check_keywords(nested, &format!("{path}/{keyword}"))?;
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Returns the name of the JSON type of the value, as used by the `type` keyword.
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(number) if number.is_i64() || number.is_u64() => "integer",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+/// Returns whether the value is of the type with the given name. Integers are also numbers.
+fn has_type(value: &Value, expected: &str) -> bool {
+    let actual = type_name(value);
+    actual == expected || (expected == "number" && actual == "integer")
+}
+
+/// Returns the schema referred to by a `$ref` of the form `#/$defs/<name>`.
+fn resolve_ref<'a>(root: &'a Value, reference: &str) -> Option<&'a Value> {
+    let name = reference.strip_prefix("#/$defs/")?;
+    root.get("$defs")?.get(name)
+}
+
+/// Validates the value at `path` against the schema and adds a description of every violation to
+/// `errors`. `root` is the schema containing the definitions referred to by `$ref`.
+fn validate_value(
+    root: &Value,
+    schema: &Value,
+    value: &Value,
+    path: &str,
+    errors: &mut Vec<String>,
+) {
+    let location = if path.is_empty() { "/" } else { path };
+    if let Some(reference) = schema.get("$ref").and_then(Value::as_str) {
+        match resolve_ref(root, reference) {
+            Some(resolved) => validate_value(root, resolved, value, path, errors),
+            None => errors.push(format!("{location}: unknown schema reference {reference}")),
+        }
+    }
+
+    if let Some(expected) = schema.get("type").and_then(Value::as_str) {
+        if !has_type(value, expected) {
+            errors.push(format!(
+                "{location}: expected {expected}, found {}",
+                type_name(value)
+            ));
+            return;
+        }
+    }
+
+    match value {
+        Value::String(string) => {
+            let min_length = schema.get("minLength").and_then(Value::as_u64);
+            if min_length.is_some_and(|min_length| (string.chars().count() as u64) < min_length) {
+                errors.push(format!("{location}: string is too short"));
+            }
+        }
+        Value::Array(items) => {
+            let count = items.len() as u64;
+            if let Some(min_items) = schema.get("minItems").and_then(Value::as_u64) {
+                if count < min_items {
+                    errors.push(format!("{location}: expected at least {min_items} items"));
+                }
+            }
+            if let Some(max_items) = schema.get("maxItems").and_then(Value::as_u64) {
+                if count > max_items {
+                    errors.push(format!("{location}: expected at most {max_items} items"));
+                }
+            }
+            if let Some(item_schema) = schema.get("items") {
+                for (index, item) in items.iter().enumerate() {
+                    validate_value(root, item_schema, item, &format!("{path}/{index}"), errors);
+                }
+            }
+        }
+        Value::Object(object) => {
+            let count = object.len() as u64;
+            if let Some(min_properties) = schema.get("minProperties").and_then(Value::as_u64) {
+                if count < min_properties {
+                    errors.push(format!(
+                        "{location}: expected at least {min_properties} properties"
+                    ));
+                }
+            }
+            if let Some(max_properties) = schema.get("maxProperties").and_then(Value::as_u64) {
+                if count > max_properties {
+                    errors.push(format!(
+                        "{location}: expected at most {max_properties} properties"
+                    ));
+                }
+            }
+            for required in schema
+                .get("required")
+                .and_then(Value::as_array)
+                .into_iter()
+                .flatten()
+                .filter_map(Value::as_str)
+            {
+                if !object.contains_key(required) {
+                    errors.push(format!(
+                        "{location}: missing required property \"{required}\""
+                    ));
+                }
+            }
+
+            let properties = schema.get("properties").and_then(Value::as_object);
+            for (key, property) in object {
+                let property_path = format!("{path}/{key}");
+                match (
+                    properties.and_then(|properties| properties.get(key)),
+                    schema.get("additionalProperties"),
+                ) {
+                    (Some(property_schema), _) => {
+                        validate_value(root, property_schema, property, &property_path, errors)
+                    }
+                    (None, Some(Value::Bool(false))) => {
+                        errors.push(format!("{location}: unknown property \"{key}\""))
+                    }
+                    (None, Some(additional)) if additional.is_object() => {
+                        validate_value(root, additional, property, &property_path, errors)
+                    }
+                    (None, _) => {}
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Validates the course manifest against the schema and returns the description of every
+/// violation, each prefixed with the JSON pointer to the offending value.
+pub(crate) fn validate_manifest(manifest: &Value) -> Result<Vec<String>> {
+    let schema: Value = serde_json::from_str(COURSE_MANIFEST_SCHEMA)
+        .context("failed to parse the course manifest schema")?;
+    check_keywords(&schema, "#").context("the course manifest schema cannot be validated")?;
+    let mut errors = vec![];
+    validate_value(&schema, &schema, manifest, "", &mut errors);
+    Ok(errors)
+}
+
+/// Prints the JSON Schema of the course manifests.
+pub(crate) fn print_schema() {
+    print!("{COURSE_MANIFEST_SCHEMA}");
+}
+
+/// Validates the course manifests against the schema and prints each violation. If `id` is given,
+/// only the manifest of that course is validated, otherwise all the manifests in the library are.
+pub(crate) fn validate_against_schema(root: &Path, id: Option<&str>) -> Result<()> {
+    let courses_root = library::courses_root(root)?;
+    let paths = match id {
+        Some(id) => {
+            let path = library::manifest_path(&courses_root, id);
+            if !path.exists() {
+                bail!("course does not exist at {}", path.display());
+            }
+            vec![path]
+        }
+        None => library::find_manifests(&courses_root)?,
+    };
+
+    let mut violations = 0;
+    for path in &paths {
+        let contents = fs::read_to_string(path)
+            .with_context(|| format!("failed to read course manifest at {}", path.display()))?;
+        let manifest: Value = serde_json::from_str(&contents)
+            .with_context(|| format!("failed to parse course manifest at {}", path.display()))?;
+        for error in validate_manifest(&manifest)? {
+            violations += 1;
+            println!("{}: {error}", path.display());
+        }
+    }
+
+    if violations > 0 {
+        bail!(Failure::new(
+            FailureKind::InvalidCourses,
+            format!("found {violations} schema violations")
+        ));
+    }
+    status!("{} course manifests match the schema.", paths.len());
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use anyhow::Result;
+    use serde_json::json;
+    use std::fs;
+
+    use super::{check_keywords, validate_manifest};
+    use crate::library;
+
+    /// Verifies that the manifests in this repository match the schema.
+    #[test]
+    fn repository_manifests_match_schema() -> Result<()> {
+        for path in library::find_manifests(&library::courses_root(".".as_ref())?)? {
+            let manifest = serde_json::from_str(&fs::read_to_string(&path)?)?;
+            assert_eq!(validate_manifest(&manifest)?, Vec::<String>::new());
+        }
+        Ok(())
+    }
+
+    /// Verifies that violations are reported with the path to the offending value.
+    #[test]
+    fn schema_violations() -> Result<()> {
+        let manifest = json!({
+            "id": "trane::transcription::a",
+            "authors": "The Trane Project",
+            "generator_config": {
+                "Transcription": {
+                    "inlined_passage": [],
+                    "inlined_passages": [{
+                        "asset": {"Track": {"short_id": "a"}},
+                        "intervals": {"0": ["0:00"]}
+                    }]
+                }
+            }
+        });
+        assert_eq!(
+            validate_manifest(&manifest)?,
+            vec![
+                "/authors: expected array, found string",
+                "/generator_config/Transcription: unknown property \"inlined_passage\"",
+                "/generator_config/Transcription/inlined_passages/0/asset/Track: missing required \
+                property \"track_name\"",
+                "/generator_config/Transcription/inlined_passages/0/intervals/0: expected at least \
+                2 items",
+            ]
+        );
+        Ok(())
+    }
+
+    /// Verifies that keywords the validator does not implement are rejected, wherever they appear
+    /// in the schema.
+    #[test]
+    fn unsupported_keywords() {
+        let supported = json!({
+            "type": "object",
+            "description": "A course",
+            "properties": {"id": {"type": "string", "minLength": 1}},
+            "additionalProperties": {"type": "array", "items": {"type": "string"}}
+        });
+        assert!(check_keywords(&supported, "#").is_ok());
+
+        for schema in [
+            json!({"type": "string", "enum": ["a"]}),
+            json!({"properties": {"id": {"type": "string", "pattern": "^a"}}}),
+            json!({"additionalProperties": {"items": {"oneOf": []}}}),
+            json!({"$defs": {"a": {"anyOf": []}}}),
+            json!({"type": ["string", "null"]}),
+        ] {
+            assert!(check_keywords(&schema, "#").is_err());
+        }
+    }
+}
@@ -0,0 +1,126 @@
+//! Contains the `search` subcommand, which finds courses whose ID, authors, or metadata contain a
+//! query.
+
+use std::path::Path;
+
+use anyhow::Result;
+use serde::Serialize;
+use trane::data::CourseManifest;
+use ustr::Ustr;
+
+use crate::{library, output::status, reports::ReportFormat};
+
+/// A field of a course that matched the query.
+#[derive(Debug, PartialEq, Eq, Serialize)]
+struct SearchMatch {
+    /// The ID of the course.
+    id: Ustr,
+
+    /// The name of the field that matched, e.g. `authors` or `metadata.genre`.
+    field: String,
+
+    /// The value of the field that matched.
+    value: String,
+}
+
+/// Returns the fields of the course containing the query, ignoring case. If `metadata_key` is set,
+/// only the values of that metadata key are searched.
+fn search_manifest(
+    manifest: &CourseManifest,
+    query: &str,
+    metadata_key: Option<&str>,
+) -> Vec<SearchMatch> {
+    let query = query.to_lowercase();
+    let mut candidates = vec![];
+    if metadata_key.is_none() {
+        candidates.push(("id".to_string(), manifest.id.to_string()));
+        for author in manifest.authors.as_deref().unwrap_or_default() {
+            candidates.push(("authors".to_string(), author.clone()));
+        }
+    }
+    for (key, values) in manifest.metadata.iter().flatten() {
+        if metadata_key.is_some_and(|metadata_key| metadata_key != key) {
+            continue;
+        }
+        for value in values {
+            candidates.push((format!("metadata.{key}"), value.clone()));
+        }
+    }
+
+    candidates
+        .into_iter()
+        .filter(|(_, value)| value.to_lowercase().contains(&query))
+        .map(|(field, value)| SearchMatch {
+            id: manifest.id,
+            field,
+            value,
+        })
+        .collect()
+}
+
+/// Prints the courses with a field containing the query in the given format.
+pub(crate) fn search_courses(
+    root: &Path,
+    query: &str,
+    metadata_key: Option<&str>,
+    format: ReportFormat,
+) -> Result<()> {
+    let courses_root = library::courses_root(root)?;
+    let matches = library::read_all_manifests(&courses_root)?
+        .iter()
+        .flat_map(|(_, manifest)| search_manifest(manifest, query, metadata_key))
+        .collect::<Vec<_>>();
+
+    match format {
+        ReportFormat::Plain => {
+            if matches.is_empty() {
+                status!("No courses match {query}.");
+            }
+            for search_match in &matches {
+                println!(
+                    "{}: {} = {}",
+                    search_match.id, search_match.field, search_match.value
+                );
+            }
+        }
+        ReportFormat::Json => println!("{}", serde_json::to_string_pretty(&matches)?),
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::BTreeMap;
+
+    use trane::data::CourseManifestBuilder;
+    use ustr::Ustr;
+
+    use super::search_manifest;
+
+    /// Verifies that the query is matched against the ID, authors, and metadata of the course.
+    #[test]
+    fn search_manifest_fields() {
+        let manifest = CourseManifestBuilder::default()
+            .id(Ustr::from("trane::transcription::jazz::miles_davis"))
+            .name("".to_string())
+            .authors(Some(vec!["The Trane Project".to_string()]))
+            .metadata(Some(BTreeMap::from([(
+                "genre".to_string(),
+                vec!["Jazz".to_string()],
+            )])))
+            .build()
+            .unwrap();
+
+        let fields = |query, metadata_key| {
+            search_manifest(&manifest, query, metadata_key)
+                .into_iter()
+                .map(|search_match| search_match.field)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(fields("JAZZ", None), vec!["id", "metadata.genre"]);
+        assert_eq!(fields("trane", None), vec!["id", "authors"]);
+        assert_eq!(fields("jazz", Some("genre")), vec!["metadata.genre"]);
+        assert!(fields("jazz", Some("instrument")).is_empty());
+        assert!(fields("rock", None).is_empty());
+    }
+}
@@ -0,0 +1,130 @@
+//! Contains the `summary` subcommand, which prints a single JSON document with the health of the
+//! whole library, meant to be consumed by dashboards and other tools.
+
+use std::path::Path;
+
+use anyhow::Result;
+use serde::Serialize;
+use ustr::Ustr;
+
+use crate::{
+    cache::LinkCache,
+    graph::DependencyGraph,
+    library, lint,
+    reports::{self, Completeness},
+};
+
+/// The statistics of the dependency graph of the transcription courses.
+#[derive(Debug, Default, Serialize)]
+struct GraphSummary {
+    /// The length, counted in edges, of the longest chain of dependencies.
+    max_depth: usize,
+
+    /// The number of transcription courses with no dependencies and no dependents.
+    isolated_courses: usize,
+}
+
+/// The number of transcription courses in each completeness category.
+#[derive(Debug, Default, Serialize)]
+struct CompletenessSummary {
+    /// The minimum number of passages for a course to be complete.
+    threshold: usize,
+
+    /// The number of empty courses.
+    empty: usize,
+
+    /// The number of partial courses.
+    partial: usize,
+
+    /// The number of complete courses.
+    complete: usize,
+}
+
+/// The health of the library printed by the `summary` subcommand.
+#[derive(Debug, Default, Serialize)]
+struct LibrarySummary {
+    /// The number of courses in the library, including those not generated by this tool.
+    total_courses: usize,
+
+    /// The number of transcription courses.
+    transcription_courses: usize,
+
+    /// The number of inlined passages in the transcription courses.
+    passages: usize,
+
+    /// The number of external links of the passages.
+    links: usize,
+
+    /// The number of links whose last verification, as recorded in the link cache, failed.
+    broken_links: usize,
+
+    /// The number of violations of lint rules with error severity.
+    lint_errors: usize,
+
+    /// The number of violations of lint rules with warning severity.
+    lint_warnings: usize,
+
+    /// The statistics of the dependency graph.
+    graph: GraphSummary,
+
+    /// The completeness of the transcription courses.
+    completeness: CompletenessSummary,
+}
+
+/// Prints a JSON summary of the library. Courses with at least `threshold` passages count as
+/// complete, and the lint violations of the courses matching the `ignored` patterns are skipped.
+/// The broken links are read from the link cache, so no link is verified.
+pub(crate) fn print_summary(
+    root: &Path,
+    threshold: usize,
+    known_course_series: &[String],
+    ignored: &[Ustr],
+) -> Result<()> {
+    let courses_root = library::courses_root(root)?;
+    let manifests = library::read_all_manifests(&courses_root)?;
+    let graph = DependencyGraph::from_manifests(manifests.iter().map(|(_, manifest)| manifest));
+    let cache = LinkCache::open(root)?;
+    let (lint_errors, lint_warnings) = lint::count_violations(root, known_course_series, ignored)?;
+
+    let mut summary = LibrarySummary {
+        total_courses: manifests.len(),
+        lint_errors,
+        lint_warnings,
+        graph: GraphSummary {
+            max_depth: graph.max_depth(),
+            ..Default::default()
+        },
+        completeness: CompletenessSummary {
+            threshold,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    for (_, manifest) in &manifests {
+        let Some(config) = library::transcription_config(manifest) else {
+            continue;
+        };
+        summary.transcription_courses += 1;
+        summary.passages += config.inlined_passages.len();
+        let additional_links = library::read_additional_links(&courses_root, &manifest.id)?;
+        for passages in &config.inlined_passages {
+            for link in library::passage_links(&additional_links, passages) {
+                summary.links += 1;
+                if cache.is_broken(link.url()) {
+                    summary.broken_links += 1;
+                }
+            }
+        }
+        if graph.is_isolated(manifest.id) {
+            summary.graph.isolated_courses += 1;
+        }
+        match reports::classify_course(config, threshold) {
+            Completeness::Empty => summary.completeness.empty += 1,
+            Completeness::Partial => summary.completeness.partial += 1,
+            Completeness::Complete => summary.completeness.complete += 1,
+        }
+    }
+
+    println!("{}", serde_json::to_string_pretty(&summary)?);
+    Ok(())
+}
@@ -0,0 +1,57 @@
+//! Contains the templates used to create new courses. Each template produces the starting
+//! transcription config of a different kind of course.
+
+use std::collections::HashMap;
+
+use clap::ValueEnum;
+use trane::data::course_generator::transcription::{
+    TranscriptionAsset, TranscriptionConfig, TranscriptionPassages,
+};
+
+/// The built-in templates for new courses.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub(crate) enum CourseTemplate {
+    /// A course with no passages and all the lessons enabled.
+    #[default]
+    Blank,
+
+    /// A full-song transcription, seeded with a placeholder passage to be replaced with the song.
+    Song,
+
+    /// A drill on a single instrument, which skips the singing lessons.
+    Drill,
+}
+
+impl CourseTemplate {
+    /// Returns the transcription config from which a new course using this template starts.
+    pub fn config(self) -> TranscriptionConfig {
+        let config = TranscriptionConfig {
+            transcription_dependencies: vec![],
+            passage_directory: "".to_string(),
+            inlined_passages: vec![],
+            skip_singing_lessons: false,
+            skip_advanced_lessons: false,
+        };
+        match self {
+            CourseTemplate::Blank => config,
+            CourseTemplate::Song => TranscriptionConfig {
+                inlined_passages: vec![TranscriptionPassages {
+                    asset: TranscriptionAsset::Track {
+                        short_id: "placeholder".to_string(),
+                        track_name: "Placeholder".to_string(),
+                        artist_name: None,
+                        album_name: None,
+                        duration: None,
+                        external_link: None,
+                    },
+                    intervals: HashMap::new(),
+                }],
+                ..config
+            },
+            CourseTemplate::Drill => TranscriptionConfig {
+                skip_singing_lessons: true,
+                ..config
+            },
+        }
+    }
+}
@@ -0,0 +1,50 @@
+//! Contains the `touch` subcommand, which rewrites every course manifest using the canonical
+//! formatting of this repository.
+
+use std::{fs, path::Path};
+
+use anyhow::{bail, Context, Result};
+
+use crate::{
+    changes::FileChanges,
+    exit::{Failure, FailureKind},
+    library,
+    output::status,
+};
+
+/// Reformats every course manifest whose contents differ from the canonical formatting. If `check`
+/// is true, the manifests that need to be reformatted are reported but not written, and an error is
+/// returned if there are any.
+pub(crate) fn touch_courses(root: &Path, changes: FileChanges, check: bool) -> Result<()> {
+    let courses_root = library::courses_root(root)?;
+    let mut reformatted = 0;
+    for path in library::find_manifests(&courses_root)? {
+        let contents = fs::read(&path)
+            .with_context(|| format!("failed to read course manifest at {}", path.display()))?;
+        let manifest = library::read_manifest(&path)?;
+        if library::serialize_manifest(&manifest)? == contents {
+            continue;
+        }
+
+        reformatted += 1;
+        if check {
+            println!("Course manifest at {} needs reformatting", path.display());
+        } else {
+            changes.write_manifest(&path, &manifest)?;
+            status!("Reformatted course manifest at {}", path.display());
+        }
+    }
+
+    if check {
+        if reformatted > 0 {
+            bail!(Failure::new(
+                FailureKind::InvalidCourses,
+                format!("found {reformatted} course manifests that need reformatting")
+            ));
+        }
+        status!("All course manifests are formatted.");
+    } else {
+        status!("Reformatted {reformatted} course manifests.");
+    }
+    Ok(())
+}
@@ -0,0 +1,144 @@
+//! Contains the `tree` subcommand, which prints the courses as a hierarchy grouped by the segments
+//! of their IDs.
+
+use std::{collections::BTreeMap, path::Path};
+
+use anyhow::Result;
+use ustr::Ustr;
+
+use crate::library::{self, COURSE_ID_PREFIX};
+
+/// A segment of the course IDs along with the segments that follow it.
+#[derive(Debug, Default)]
+struct TreeNode {
+    /// The segments following this one, keyed by name.
+    children: BTreeMap<String, TreeNode>,
+
+    /// The number of courses whose IDs start with the segments up to and including this one.
+    course_count: usize,
+}
+
+impl TreeNode {
+    /// Adds the course with the given ID segments under this node.
+    fn insert<'a>(&mut self, mut segments: impl Iterator<Item = &'a str>) {
+        self.course_count += 1;
+        if let Some(segment) = segments.next() {
+            self.children
+                .entry(segment.to_string())
+                .or_default()
+                .insert(segments);
+        }
+    }
+
+    /// Renders the children of this node, down to the given depth if set. Nodes whose children are
+    /// not shown because of the depth are annotated with the number of courses under them.
+    fn render(&self, prefix: &str, depth: Option<usize>, output: &mut String) {
+        if depth == Some(0) {
+            return;
+        }
+        let child_depth = depth.map(|depth| depth - 1);
+        for (i, (name, child)) in self.children.iter().enumerate() {
+            let is_last = i + 1 == self.children.len();
+            let (branch, indent) = if is_last {
+                ("└── ", "    ")
+            } else {
+                ("├── ", "│   ")
+            };
+            output.push_str(&format!("{prefix}{branch}{name}"));
+            if child_depth == Some(0) && !child.children.is_empty() {
+                let noun = if child.course_count == 1 {
+                    "course"
+                } else {
+                    "courses"
+                };
+                output.push_str(&format!(" ({} {noun})", child.course_count));
+            }
+            output.push('\n');
+            child.render(&format!("{prefix}{indent}"), child_depth, output);
+        }
+    }
+}
+
+/// Returns the tree of the given courses. The shared `trane::transcription` prefix is the root of
+/// the tree, and `depth` limits the number of levels printed below it. Courses whose IDs do not
+/// start with the prefix are left out.
+fn render_tree(course_ids: &[Ustr], depth: Option<usize>) -> String {
+    let mut root = TreeNode::default();
+    for id in course_ids {
+        if let Some(id) = id.strip_prefix(COURSE_ID_PREFIX) {
+            root.insert(id.split("::"));
+        }
+    }
+    let mut output = format!("{}\n", COURSE_ID_PREFIX.trim_end_matches("::"));
+    root.render("", depth, &mut output);
+    output
+}
+
+/// Prints all the courses in the library as a tree, down to the given depth if set. The courses
+/// whose IDs do not start with the `trane::transcription::` prefix are only counted.
+pub(crate) fn print_tree(root: &Path, depth: Option<usize>) -> Result<()> {
+    let courses_root = library::courses_root(root)?;
+    let course_ids = library::read_all_manifests(&courses_root)?
+        .into_iter()
+        .map(|(_, manifest)| manifest.id)
+        .collect::<Vec<_>>();
+    print!("{}", render_tree(&course_ids, depth));
+    let other_courses = course_ids
+        .iter()
+        .filter(|id| !id.starts_with(COURSE_ID_PREFIX))
+        .count();
+    if other_courses > 0 {
+        eprintln!(
+            "Warning: left out {other_courses} courses whose ids do not start with \
+            {COURSE_ID_PREFIX}"
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use ustr::Ustr;
+
+    use super::render_tree;
+
+    /// Verifies that the courses are grouped by their shared prefixes, that only the first
+    /// `trane::transcription` prefix is removed, that other courses are left out, and that the
+    /// depth limits the levels printed.
+    #[test]
+    fn render_course_tree() {
+        let ids = [
+            "trane::transcription::folk::adrianne_lenker::songs",
+            "trane::transcription::jazz::miles_davis::kind_of_blue",
+            "trane::transcription::jazz::miles_davis::so_what",
+            "trane::transcription::trane::transcription::nested",
+            "trane::piano::scales",
+        ]
+        .map(Ustr::from);
+        let expected = [
+            "trane::transcription",
+            "├── folk",
+            "│   └── adrianne_lenker",
+            "│       └── songs",
+            "├── jazz",
+            "│   └── miles_davis",
+            "│       ├── kind_of_blue",
+            "│       └── so_what",
+            "└── trane",
+            "    └── transcription",
+            "        └── nested",
+        ];
+        assert_eq!(render_tree(&ids, None), expected.join("\n") + "\n");
+        assert_eq!(
+            render_tree(&ids, Some(1)),
+            [
+                "trane::transcription",
+                "├── folk (1 course)",
+                "├── jazz (2 courses)",
+                "└── trane (1 course)",
+            ]
+            .join("\n")
+                + "\n"
+        );
+    }
+}